@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use serde_json::{Map, Value};
+use super::{Passport, FIELD_KEYS, split_entry};
+
+const EXTRAS: &str = "extras";
+
+#[derive(Debug)]
+pub enum ImportError {
+    Json(usize, serde_json::Error),
+    NotAnObject(usize),
+    NotAString(usize, String),
+    Csv(usize, String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Json(line, e) => write!(f, "Line {}: {}", line, e),
+            ImportError::NotAnObject(line) => write!(f, "Line {}: expected an object", line),
+            ImportError::NotAString(line, key) => write!(f, "Line {}: {} is not a string", line, key),
+            ImportError::Csv(line, message) => write!(f, "Line {}: {}", line, message),
+        }
+    }
+}
+
+impl Passport {
+    fn known_values(&self) -> impl Iterator<Item=(&String, &String)> {
+        self.values.iter().filter(|(key, _)| is_known(key))
+    }
+
+    fn extra_values(&self) -> impl Iterator<Item=(&String, &String)> {
+        self.values.iter().filter(|(key, _)| !is_known(key))
+    }
+
+    /// Known fields become top level keys, anything else goes into an `extras` object.
+    pub fn to_json(&self) -> String {
+        let mut object = Map::new();
+        for (key, value) in self.known_values() {
+            object.insert(key.clone(), Value::String(value.clone()));
+        }
+        let extras: Map<String, Value> = self.extra_values()
+            .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+            .collect();
+        if !extras.is_empty() {
+            object.insert(EXTRAS.to_string(), Value::Object(extras));
+        }
+        Value::Object(object).to_string()
+    }
+
+    fn from_json(line_number: usize, s: &str) -> Result<Self, ImportError> {
+        let value: Value = serde_json::from_str(s).map_err(|e| ImportError::Json(line_number, e))?;
+        let object = match value {
+            Value::Object(object) => object,
+            _ => return Err(ImportError::NotAnObject(line_number)),
+        };
+
+        let mut values = BTreeMap::new();
+        for (key, value) in object {
+            match value {
+                Value::String(s) if key != EXTRAS => {
+                    values.insert(key, s);
+                }
+                Value::Object(extras) if key == EXTRAS => {
+                    for (key, value) in extras {
+                        match value {
+                            Value::String(s) => values.insert(key, s),
+                            _ => return Err(ImportError::NotAString(line_number, key)),
+                        };
+                    }
+                }
+                _ => return Err(ImportError::NotAString(line_number, key)),
+            }
+        }
+        Ok(Passport::new(values))
+    }
+
+    fn to_csv_row(&self) -> String {
+        let mut cells: Vec<String> = FIELD_KEYS.iter()
+            .map(|key| match self.values.get(*key) {
+                Some(value) if value.is_empty() => "\"\"".to_string(),
+                Some(value) => escape_csv(value),
+                None => String::new(),
+            })
+            .collect();
+        let extras = self.extra_values()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ");
+        cells.push(escape_csv(&extras));
+        cells.join(",")
+    }
+}
+
+fn is_known(key: &str) -> bool {
+    FIELD_KEYS.contains(&key)
+}
+
+pub fn to_json_lines(passports: &[Passport]) -> String {
+    passports.iter()
+        .map(|p| p.to_json() + "\n")
+        .collect()
+}
+
+pub fn from_json_lines(s: &str) -> Result<Vec<Passport>, ImportError> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| Passport::from_json(i + 1, line))
+        .collect()
+}
+
+/// One column per known field followed by an `extras` column holding the remaining
+/// fields in the original `key:value` format. An empty cell means the field is missing,
+/// a field with an empty value is written as `""`.
+pub fn to_csv(passports: &[Passport]) -> String {
+    let mut out = FIELD_KEYS.join(",");
+    out.push(',');
+    out.push_str(EXTRAS);
+    out.push('\n');
+    for passport in passports {
+        out.push_str(&passport.to_csv_row());
+        out.push('\n');
+    }
+    out
+}
+
+pub fn from_csv(s: &str) -> Result<Vec<Passport>, ImportError> {
+    let mut lines = s.lines().enumerate();
+    let header = match lines.next() {
+        Some((_, header)) => split_csv(1, header)?.into_iter().map(Option::unwrap_or_default).collect::<Vec<_>>(),
+        None => return Ok(Vec::new()),
+    };
+    for column in &header {
+        if !is_known(column) && column != EXTRAS {
+            return Err(ImportError::Csv(1, format!("Unknown column: {}", column)));
+        }
+    }
+
+    let mut out = Vec::new();
+    for (i, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+        let cells = split_csv(i + 1, line)?;
+        if cells.len() != header.len() {
+            return Err(ImportError::Csv(i + 1, format!("Expected {} cells, got {}", header.len(), cells.len())));
+        }
+
+        let mut values = BTreeMap::new();
+        for (column, cell) in header.iter().zip(cells) {
+            if column == EXTRAS {
+                for entry in cell.unwrap_or_default().split_whitespace() {
+                    let (key, value) = split_entry(entry);
+                    values.insert(key.to_string(), value.to_string());
+                }
+            } else if let Some(value) = cell {
+                values.insert(column.clone(), value);
+            }
+        }
+        out.push(Passport::new(values));
+    }
+    Ok(out)
+}
+
+/// Passports in the original format, separated by blank lines.
+pub fn to_records(passports: &[Passport]) -> String {
+    passports.iter()
+        .map(|p| p.to_record() + "\n")
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn from_records(s: &str) -> Vec<Passport> {
+    s.split("\n\n")
+        .map(|record| record.lines().collect::<Vec<_>>())
+        .filter(|record| !record.is_empty())
+        .map(|record| Passport::from_record(&record))
+        .collect()
+}
+
+fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// The cells of a line, `None` for an empty cell that wasn't quoted.
+fn split_csv(line_number: usize, line: &str) -> Result<Vec<Option<String>>, ImportError> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut was_quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.is_empty() => {
+                quoted = true;
+                was_quoted = true;
+            }
+            ',' if !quoted => {
+                cells.push(csv_cell(std::mem::take(&mut cell), was_quoted));
+                was_quoted = false;
+            }
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err(ImportError::Csv(line_number, "Unterminated quote".to_string()));
+    }
+    cells.push(csv_cell(cell, was_quoted));
+    Ok(cells)
+}
+
+fn csv_cell(cell: String, quoted: bool) -> Option<String> {
+    if cell.is_empty() && !quoted {
+        None
+    } else {
+        Some(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day04::read_passports;
+
+    fn passport(s: &str) -> Passport {
+        Passport::from_record(&[s])
+    }
+
+    #[test]
+    fn json_line() {
+        let p = passport("hgt:183cm byr:1937 foo:a,\"b\"");
+        assert_eq!(p.to_json(), r#"{"byr":"1937","extras":{"foo":"a,\"b\""},"hgt":"183cm"}"#);
+        assert_eq!(Passport::from_json(1, &p.to_json()).unwrap(), p);
+    }
+
+    #[test]
+    fn csv_row() {
+        let p = passport("hgt:183cm byr:1937 foo:a,\"b\" bar:1");
+        assert_eq!(p.to_csv_row(), r#"1937,,,183cm,,,,,"bar:1 foo:a,""b""""#);
+        assert_eq!(from_csv(&to_csv(std::slice::from_ref(&p))).unwrap(), vec![p]);
+    }
+
+    #[test]
+    fn csv_empty_values() {
+        let p = passport("byr: hgt:183cm foo:");
+        assert_eq!(p.to_csv_row(), r#""",,,183cm,,,,,foo:"#);
+        assert_eq!(from_csv(&to_csv(std::slice::from_ref(&p))).unwrap(), vec![p]);
+        assert_eq!(from_csv("byr,pid\n,\"\"\n").unwrap(), vec![passport("pid:")]);
+    }
+
+    #[test]
+    fn csv_columns_in_any_order() {
+        let passports = from_csv("pid,extras,byr\n000000001,,1980\n,x:y,\n").unwrap();
+        assert_eq!(passports, vec![passport("pid:000000001 byr:1980"), passport("x:y")]);
+    }
+
+    #[test]
+    fn import_errors() {
+        assert!(matches!(from_json_lines("{}\n[1]"), Err(ImportError::NotAnObject(2))));
+        assert!(matches!(from_json_lines(r#"{"byr":1980}"#), Err(ImportError::NotAString(1, _))));
+        assert!(matches!(from_json_lines("{"), Err(ImportError::Json(1, _))));
+        assert!(matches!(from_csv("byr,foo\n"), Err(ImportError::Csv(1, _))));
+        assert!(matches!(from_csv("byr,pid\n1980\n"), Err(ImportError::Csv(2, _))));
+        assert!(matches!(from_csv("byr\n\"1980\n"), Err(ImportError::Csv(2, _))));
+        assert_eq!(from_csv("byr,foo\n").unwrap_err().to_string(), "Line 1: Unknown column: foo");
+        assert_eq!(from_json_lines("{}\n[]").unwrap_err().to_string(), "Line 2: expected an object");
+    }
+
+    #[test]
+    fn round_trip() {
+        let passports = read_passports("./src/day04/input.txt").unwrap();
+
+        assert_eq!(from_json_lines(&to_json_lines(&passports)).unwrap(), passports);
+        assert_eq!(from_csv(&to_csv(&passports)).unwrap(), passports);
+        assert_eq!(from_records(&to_records(&passports)), passports);
+    }
+}
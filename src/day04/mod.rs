@@ -1,8 +1,16 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::path::Path;
 use std::str::FromStr;
 use crate::day04::Line::{Empty, Fields};
 use crate::day04::Field::{IssueYear, BirthYear, ExpirationYear, Height, HairColor, EyeColor, PassportId, CountryId};
 use regex::bytes::Regex;
+use crate::util::read_records;
+
+mod export;
+mod repair;
+
+const FIELD_KEYS: [&str; 8] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
 
 #[derive(Debug, Eq, PartialEq)]
 enum Line {
@@ -62,14 +70,41 @@ impl FromStr for Field {
             "ecl" if valid_ecl(data) => Ok(EyeColor),
             "pid" if valid_pid(data) => Ok(PassportId),
             "cid" => Ok(CountryId),
-            _ => {
-                println!("Invalid: {}", s);
-                Err(())
-            },
+            _ => Err(()),
+        }
+    }
+}
+
+impl Field {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "byr" => Some(BirthYear),
+            "iyr" => Some(IssueYear),
+            "eyr" => Some(ExpirationYear),
+            "hgt" => Some(Height),
+            "hcl" => Some(HairColor),
+            "ecl" => Some(EyeColor),
+            "pid" => Some(PassportId),
+            "cid" => Some(CountryId),
+            _ => None,
         }
     }
 }
 
+/// Validates the value of a known field, unknown fields are always valid.
+fn valid_value(key: &str, data: &str) -> bool {
+    match key {
+        "byr" => valid_byr(data),
+        "iyr" => valid_iyr(data),
+        "eyr" => valid_eyr(data),
+        "hgt" => valid_hgt(data),
+        "hcl" => valid_hcl(data),
+        "ecl" => valid_ecl(data),
+        "pid" => valid_pid(data),
+        _ => true,
+    }
+}
+
 fn valid_pid(data: &str) -> bool {
     let re = Regex::new(r"^[0-9]{9}$").unwrap();
     re.is_match(data.as_bytes())
@@ -130,12 +165,40 @@ fn valid_eyr(data: &str) -> bool {
     false
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 struct Passport {
-    fields: HashSet<Field>
+    fields: HashSet<Field>,
+    values: BTreeMap<String, String>,
 }
 
 impl Passport {
+    fn new(values: BTreeMap<String, String>) -> Self {
+        let fields = values.iter()
+            .filter(|(key, value)| valid_value(key, value))
+            .filter_map(|(key, _)| Field::from_key(key))
+            .collect();
+        Passport { fields, values }
+    }
+
+    fn from_record<T: AsRef<str>>(record: &[T]) -> Self {
+        let mut values = BTreeMap::new();
+        for line in record {
+            for entry in line.as_ref().split_whitespace() {
+                let (key, value) = split_entry(entry);
+                values.insert(key.to_string(), value.to_string());
+            }
+        }
+        Passport::new(values)
+    }
+
+    /// The passport in the original `key:value` format, on a single line.
+    fn to_record(&self) -> String {
+        self.values.iter()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     fn is_valid(&self) -> bool {
         let expected_fields = vec![BirthYear, IssueYear, ExpirationYear, Height, HairColor, EyeColor, PassportId];
 
@@ -149,6 +212,14 @@ impl Passport {
     }
 }
 
+fn split_entry(entry: &str) -> (&str, &str) {
+    match entry.find(':') {
+        Some(i) => (&entry[..i], &entry[i + 1..]),
+        None => (entry, ""),
+    }
+}
+
+/// Only keeps the fields that validated, use `read_passports` to keep the values as well.
 fn parse_passports(lines: Vec<Line>) -> Vec<Passport> {
     let mut out = Vec::new();
 
@@ -162,18 +233,23 @@ fn parse_passports(lines: Vec<Line>) -> Vec<Passport> {
                 }
             },
             Empty => {
-                out.push(Passport { fields: fields.clone() });
+                out.push(Passport { fields: fields.clone(), values: BTreeMap::new() });
                 fields.clear();
             }
         }
     }
     if !fields.is_empty() {
-        out.push( Passport { fields });
+        out.push( Passport { fields, values: BTreeMap::new() });
     }
 
     out
 }
 
+fn read_passports<P: AsRef<Path>>(filename: P) -> io::Result<Vec<Passport>> {
+    let records = read_records(filename)?;
+    Ok(records.iter().map(|record| Passport::from_record(record)).collect())
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -209,4 +285,24 @@ mod tests {
             println!("{}: {:?}", passport.is_valid(), passport);
         }
     }
+
+    #[test]
+    fn read_passports_keeps_values() {
+        let passports = read_passports("./src/day04/input.txt").unwrap();
+        let first = &passports[0];
+
+        assert_eq!(first.values["hgt"], "167cm");
+        assert_eq!(first.values.len(), 7);
+        assert!(first.is_valid());
+        assert_eq!(first.to_record(), "byr:1971 ecl:hzl eyr:2023 hcl:#b6652a hgt:167cm iyr:2019 pid:112040163");
+        assert_eq!(passports.iter().filter(|p| p.is_valid()).count(), 101);
+
+        let lines: Vec<Line> = read_file("./src/day04/input.txt").unwrap();
+        let valid = parse_passports(lines).iter().filter(|p| p.is_valid()).count();
+        assert_eq!(valid, 101);
+
+        let invalid = Passport::from_record(&["byr:1900 cid:1 foo:bar"]);
+        assert_eq!(invalid.fields, hash_set(vec![CountryId]));
+        assert_eq!(invalid.values.len(), 3);
+    }
 }
\ No newline at end of file
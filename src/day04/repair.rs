@@ -0,0 +1,288 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use super::{Passport, valid_value};
+use super::export::to_records;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Fix {
+    pub key: String,
+    pub from: String,
+    pub to: String,
+    pub reason: &'static str,
+    pub confidence: Confidence,
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} -> {}:{} ({}, {:?} confidence)",
+               self.key, self.from, self.key, self.to, self.reason, self.confidence)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FieldCheck {
+    Valid,
+    Invalid,
+    ValidAfter(Fix),
+}
+
+const EYE_COLOR_NAMES: [(&str, &str); 8] = [
+    ("amber", "amb"),
+    ("blue", "blu"),
+    ("brown", "brn"),
+    ("gray", "gry"),
+    ("grey", "gry"),
+    ("green", "grn"),
+    ("hazel", "hzl"),
+    ("other", "oth"),
+];
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_hex(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Possible corrections of a value, most confident first. They are not necessarily valid.
+fn candidates(key: &str, value: &str) -> Vec<(String, Confidence, &'static str)> {
+    use Confidence::*;
+
+    let lower = value.trim().to_lowercase();
+    let mut out = Vec::new();
+    if lower != value {
+        out.push((lower.clone(), High, "lowercase"));
+    }
+
+    match key {
+        "hgt" => {
+            if is_digits(&lower) {
+                out.push((format!("{}cm", lower), High, "add missing unit"));
+                out.push((format!("{}in", lower), High, "add missing unit"));
+            }
+            let number: String = lower.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let unit = lower[number.len()..].trim();
+            match unit {
+                "inch" | "inches" | "\"" => out.push((format!("{}in", number), High, "normalise unit")),
+                "cms" | "centimeter" | "centimeters" | "centimetre" | "centimetres" =>
+                    out.push((format!("{}cm", number), High, "normalise unit")),
+                "cm" => out.push((format!("{}in", number), Medium, "swap unit")),
+                "in" => out.push((format!("{}cm", number), Medium, "swap unit")),
+                _ => {}
+            }
+        }
+        "hcl" => {
+            if lower.len() == 6 && is_hex(&lower) {
+                out.push((format!("#{}", lower), High, "add missing #"));
+            }
+            if lower.len() == 4 && lower.starts_with('#') && is_hex(&lower[1..]) {
+                let expanded: String = lower[1..].chars().flat_map(|c| vec![c, c]).collect();
+                out.push((format!("#{}", expanded), Low, "expand short colour"));
+            }
+        }
+        "ecl" => {
+            for (name, code) in &EYE_COLOR_NAMES {
+                if lower == *name {
+                    out.push((code.to_string(), High, "use eye colour code"));
+                }
+            }
+            if lower.len() > 3 && lower.is_char_boundary(3) {
+                out.push((lower[..3].to_string(), Medium, "truncate to code"));
+            }
+        }
+        "pid" => {
+            let digits: String = lower.chars().filter(|c| !"-. ".contains(*c)).collect();
+            if digits != lower {
+                out.push((digits, Medium, "remove separators"));
+            }
+            if is_digits(&lower) && lower.len() < 9 {
+                out.push((format!("{:0>9}", lower), Low, "pad with zeros"));
+            }
+        }
+        "byr" | "iyr" | "eyr" if lower.len() == 2 && is_digits(&lower) => {
+            out.push((format!("19{}", lower), Low, "expand two digit year"));
+            out.push((format!("20{}", lower), Low, "expand two digit year"));
+        }
+        _ => {}
+    }
+
+    out.sort_by_key(|candidate| Reverse(candidate.1));
+    out
+}
+
+/// The most confident fix that makes an invalid value valid.
+pub fn suggest_fix(key: &str, value: &str) -> Option<Fix> {
+    if valid_value(key, value) {
+        return None;
+    }
+    candidates(key, value).into_iter()
+        .find(|(candidate, _, _)| valid_value(key, candidate))
+        .map(|(to, confidence, reason)| Fix {
+            key: key.to_string(),
+            from: value.to_string(),
+            to,
+            reason,
+            confidence,
+        })
+}
+
+pub fn check_field(key: &str, value: &str) -> FieldCheck {
+    if valid_value(key, value) {
+        FieldCheck::Valid
+    } else if let Some(fix) = suggest_fix(key, value) {
+        FieldCheck::ValidAfter(fix)
+    } else {
+        FieldCheck::Invalid
+    }
+}
+
+impl Passport {
+    pub fn suggest_fixes(&self) -> Vec<Fix> {
+        self.values.iter()
+            .filter_map(|(key, value)| suggest_fix(key, value))
+            .collect()
+    }
+
+    pub fn with_fixes(&self, fixes: &[Fix]) -> Passport {
+        let mut values = self.values.clone();
+        for fix in fixes {
+            if values.get(&fix.key) == Some(&fix.from) {
+                values.insert(fix.key.clone(), fix.to.clone());
+            }
+        }
+        Passport::new(values)
+    }
+
+    /// The fixes that would turn an invalid passport into a valid one, if there are any.
+    pub fn valid_after(&self, min_confidence: Confidence) -> Option<Vec<Fix>> {
+        if self.is_valid() {
+            return None;
+        }
+        let fixes: Vec<Fix> = self.suggest_fixes().into_iter()
+            .filter(|fix| fix.confidence >= min_confidence)
+            .collect();
+        if !fixes.is_empty() && self.with_fixes(&fixes).is_valid() {
+            Some(fixes)
+        } else {
+            None
+        }
+    }
+}
+
+/// Applies every fix of at least `min_confidence`, returning the fixed passports and the fixes
+/// applied to each of them.
+pub fn normalise(passports: &[Passport], min_confidence: Confidence) -> Vec<(Passport, Vec<Fix>)> {
+    passports.iter()
+        .map(|passport| {
+            let fixes: Vec<Fix> = passport.suggest_fixes().into_iter()
+                .filter(|fix| fix.confidence >= min_confidence)
+                .collect();
+            (passport.with_fixes(&fixes), fixes)
+        })
+        .collect()
+}
+
+/// Writes the normalised passports in the original format, returning the number of fixes applied.
+pub fn write_corrected<P: AsRef<Path>>(filename: P, passports: &[Passport], min_confidence: Confidence) -> io::Result<usize> {
+    let normalised = normalise(passports, min_confidence);
+    let fix_count = normalised.iter().map(|(_, fixes)| fixes.len()).sum();
+    let corrected: Vec<Passport> = normalised.into_iter().map(|(passport, _)| passport).collect();
+    fs::write(filename, to_records(&corrected))?;
+    Ok(fix_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Confidence::*;
+    use crate::day04::read_passports;
+
+    fn fixed(key: &str, value: &str) -> Option<(String, Confidence)> {
+        suggest_fix(key, value).map(|fix| (fix.to, fix.confidence))
+    }
+
+    #[test]
+    fn height() {
+        assert_eq!(fixed("hgt", "170"), Some(("170cm".to_string(), High)));
+        assert_eq!(fixed("hgt", "70"), Some(("70in".to_string(), High)));
+        assert_eq!(fixed("hgt", "70 inches"), Some(("70in".to_string(), High)));
+        assert_eq!(fixed("hgt", "180CM"), Some(("180cm".to_string(), High)));
+        assert_eq!(fixed("hgt", "190in"), Some(("190cm".to_string(), Medium)));
+        assert_eq!(fixed("hgt", "170cm"), None);
+        assert_eq!(fixed("hgt", "20"), None);
+    }
+
+    #[test]
+    fn hair_color() {
+        assert_eq!(fixed("hcl", "123abc"), Some(("#123abc".to_string(), High)));
+        assert_eq!(fixed("hcl", "#123ABC"), Some(("#123abc".to_string(), High)));
+        assert_eq!(fixed("hcl", "#1a2"), Some(("#11aa22".to_string(), Low)));
+        assert_eq!(fixed("hcl", "z"), None);
+    }
+
+    #[test]
+    fn eye_color() {
+        assert_eq!(fixed("ecl", "blue"), Some(("blu".to_string(), High)));
+        assert_eq!(fixed("ecl", "Grey"), Some(("gry".to_string(), High)));
+        assert_eq!(fixed("ecl", "hzlx"), Some(("hzl".to_string(), Medium)));
+        assert_eq!(fixed("ecl", "zzz"), None);
+    }
+
+    #[test]
+    fn passport_id_and_years() {
+        assert_eq!(fixed("pid", "123-456-789"), Some(("123456789".to_string(), Medium)));
+        assert_eq!(fixed("pid", "1234567"), Some(("001234567".to_string(), Low)));
+        assert_eq!(fixed("byr", "87"), Some(("1987".to_string(), Low)));
+        assert_eq!(fixed("eyr", "25"), Some(("2025".to_string(), Low)));
+        assert_eq!(fixed("cid", "anything"), None);
+    }
+
+    #[test]
+    fn report_field() {
+        assert_eq!(check_field("ecl", "blu"), FieldCheck::Valid);
+        assert_eq!(check_field("ecl", "zzz"), FieldCheck::Invalid);
+        match check_field("ecl", "blue") {
+            FieldCheck::ValidAfter(fix) => assert_eq!(fix.to_string(), "ecl:blue -> ecl:blu (use eye colour code, High confidence)"),
+            other => panic!("Unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn passport_valid_after_fixes() {
+        let passport = Passport::from_record(&["ecl:blue pid:123456789 eyr:2022 hcl:123abc byr:1980 iyr:2015 hgt:170"]);
+        assert!(!passport.is_valid());
+        assert_eq!(passport.valid_after(Medium).map(|fixes| fixes.len()), Some(3));
+
+        let passport = Passport::from_record(&["ecl:blu pid:1234567 eyr:2022 hcl:#123abc byr:1980 iyr:2015 hgt:170cm"]);
+        assert_eq!(passport.valid_after(Medium), None);
+        assert!(passport.valid_after(Low).is_some());
+    }
+
+    #[test]
+    fn write_corrected_file() {
+        let passports = read_passports("./src/day04/input.txt").unwrap();
+        let filename = std::env::temp_dir().join("day04_corrected.txt");
+
+        let fix_count = write_corrected(&filename, &passports, High).unwrap();
+        let corrected = read_passports(&filename).unwrap();
+        fs::remove_file(&filename).unwrap();
+
+        let valid_before = passports.iter().filter(|p| p.is_valid()).count();
+        let valid_after = corrected.iter().filter(|p| p.is_valid()).count();
+        println!("Applied {} fixes, valid passports {} -> {}", fix_count, valid_before, valid_after);
+        assert!(fix_count > 0);
+        assert_eq!(corrected.len(), passports.len());
+        assert!(valid_after >= valid_before);
+    }
+}
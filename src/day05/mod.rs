@@ -1,25 +1,150 @@
+use std::fmt;
 use std::str::FromStr;
 
+mod seat_map;
+
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
 struct Seat(u32);
 
 impl Seat {
-    fn new(s: &str) -> Self {
-        let bin_str = s
-            .replace("F", "0")
-            .replace("B", "1")
-            .replace("L", "0")
-            .replace("R", "1");
-        let seat_id = u32::from_str_radix(&bin_str, 2).unwrap();
-        Seat(seat_id)
+    fn new(s: &str) -> Result<Self, SeatError> {
+        Geometry::default().decode(s)
+    }
+
+    fn encode(&self) -> Result<String, SeatError> {
+        Geometry::default().encode(*self)
     }
 }
 
 impl FromStr for Seat {
-    type Err = ();
+    type Err = SeatError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Seat::new(s))
+        Seat::new(s)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum SeatError {
+    InvalidLength { expected: usize, actual: usize },
+    InvalidCharacter { position: usize, character: char },
+    OutOfRange(u32),
+    RowOutOfRange(u32),
+    ColumnOutOfRange(u32),
+    /// Seat IDs are `u32`, so a pass can have at most 31 bits.
+    UnsupportedGeometry { row_bits: u32, column_bits: u32 },
+}
+
+impl fmt::Display for SeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeatError::InvalidLength { expected, actual } =>
+                write!(f, "Expected {} characters, got {}", expected, actual),
+            SeatError::InvalidCharacter { position, character } =>
+                write!(f, "Invalid character '{}' at position {}", character, position),
+            SeatError::OutOfRange(value) => write!(f, "{} is out of range", value),
+            SeatError::RowOutOfRange(row) => write!(f, "Row {} is out of range", row),
+            SeatError::ColumnOutOfRange(column) => write!(f, "Column {} is out of range", column),
+            SeatError::UnsupportedGeometry { row_bits, column_bits } =>
+                write!(f, "{} row bits and {} column bits is more than 31", row_bits, column_bits),
+        }
+    }
+}
+
+/// The number of bits used for rows (F/B) and columns (L/R) in a boarding pass.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+struct Geometry {
+    row_bits: u32,
+    column_bits: u32,
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Geometry { row_bits: 7, column_bits: 3 }
+    }
+}
+
+impl Geometry {
+    fn new(row_bits: u32, column_bits: u32) -> Result<Self, SeatError> {
+        match row_bits.checked_add(column_bits) {
+            Some(bits) if bits <= 31 => Ok(Self { row_bits, column_bits }),
+            _ => Err(SeatError::UnsupportedGeometry { row_bits, column_bits }),
+        }
+    }
+
+    fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    fn columns(&self) -> u32 {
+        1 << self.column_bits
+    }
+
+    fn pass_length(&self) -> usize {
+        (self.row_bits + self.column_bits) as usize
+    }
+
+    fn seat_count(&self) -> u32 {
+        self.rows() * self.columns()
+    }
+
+    fn seat(&self, row: u32, column: u32) -> Result<Seat, SeatError> {
+        if row >= self.rows() {
+            return Err(SeatError::RowOutOfRange(row));
+        }
+        if column >= self.columns() {
+            return Err(SeatError::ColumnOutOfRange(column));
+        }
+        Ok(Seat(row * self.columns() + column))
+    }
+
+    fn row(&self, seat: Seat) -> u32 {
+        seat.0 / self.columns()
+    }
+
+    fn column(&self, seat: Seat) -> u32 {
+        seat.0 % self.columns()
+    }
+
+    fn decode(&self, s: &str) -> Result<Seat, SeatError> {
+        let actual = s.chars().count();
+        if actual != self.pass_length() {
+            return Err(SeatError::InvalidLength { expected: self.pass_length(), actual });
+        }
+
+        let mut seat_id = 0;
+        for (position, character) in s.chars().enumerate() {
+            let is_row = position < self.row_bits as usize;
+            let bit = match character {
+                'F' if is_row => 0,
+                'B' if is_row => 1,
+                'L' if !is_row => 0,
+                'R' if !is_row => 1,
+                _ => return Err(SeatError::InvalidCharacter { position, character }),
+            };
+            seat_id = seat_id << 1 | bit;
+        }
+        Ok(Seat(seat_id))
+    }
+
+    fn encode(&self, seat: Seat) -> Result<String, SeatError> {
+        if seat.0 >= self.seat_count() {
+            return Err(SeatError::OutOfRange(seat.0));
+        }
+
+        let length = self.pass_length();
+        let pass = (0..length)
+            .map(|position| {
+                let bit = seat.0 >> (length - 1 - position) & 1;
+                match (position < self.row_bits as usize, bit) {
+                    (true, 0) => 'F',
+                    (true, _) => 'B',
+                    (false, 0) => 'L',
+                    (false, _) => 'R',
+                }
+            })
+            .collect();
+        Ok(pass)
     }
 }
 
@@ -30,9 +155,54 @@ mod tests {
 
     #[test]
     fn convert_to_seat_id() {
-        assert_eq!(Seat::new("BFFFBBFRRR"), Seat(567));
-        assert_eq!(Seat::new("FFFBBBFRRR"), Seat(119));
-        assert_eq!(Seat::new("BBFFBBFRLL"), Seat(820));
+        assert_eq!(Seat::new("BFFFBBFRRR"), Ok(Seat(567)));
+        assert_eq!(Seat::new("FFFBBBFRRR"), Ok(Seat(119)));
+        assert_eq!(Seat::new("BBFFBBFRLL"), Ok(Seat(820)));
+    }
+
+    #[test]
+    fn invalid_boarding_passes() {
+        assert_eq!(Seat::new("BFFFBBFRR"), Err(SeatError::InvalidLength { expected: 10, actual: 9 }));
+        assert_eq!(Seat::new("BFFFBBFRRRR"), Err(SeatError::InvalidLength { expected: 10, actual: 11 }));
+        assert_eq!(Seat::new("BFFXBBFRRR"), Err(SeatError::InvalidCharacter { position: 3, character: 'X' }));
+        assert_eq!(Seat::new("BFFFBBFRRF"), Err(SeatError::InvalidCharacter { position: 9, character: 'F' }));
+        assert_eq!(Seat::new("BFFFBBRRRR"), Err(SeatError::InvalidCharacter { position: 6, character: 'R' }));
+    }
+
+    #[test]
+    fn encode_seat() {
+        assert_eq!(Seat(567).encode(), Ok("BFFFBBFRRR".to_string()));
+        assert_eq!(Seat(1024).encode(), Err(SeatError::OutOfRange(1024)));
+
+        let geometry = Geometry::default();
+        assert_eq!(geometry.seat(70, 7), Ok(Seat(567)));
+        assert_eq!(geometry.seat(128, 0), Err(SeatError::RowOutOfRange(128)));
+        assert_eq!(geometry.seat(0, 8), Err(SeatError::ColumnOutOfRange(8)));
+        assert_eq!(geometry.row(Seat(567)), 70);
+        assert_eq!(geometry.column(Seat(567)), 7);
+    }
+
+    #[test]
+    fn other_geometry() {
+        let geometry = Geometry::new(5, 2).unwrap();
+        let seat = geometry.seat(17, 2).unwrap();
+        assert_eq!(seat, Seat(70));
+        assert_eq!(geometry.encode(seat), Ok("BFFFBRL".to_string()));
+        assert_eq!(geometry.decode("BFFFBRL"), Ok(seat));
+        assert_eq!(geometry.decode("BFFFBBFRRR"), Err(SeatError::InvalidLength { expected: 7, actual: 10 }));
+
+        assert!(Geometry::new(28, 3).is_ok());
+        assert_eq!(Geometry::new(28, 4), Err(SeatError::UnsupportedGeometry { row_bits: 28, column_bits: 4 }));
+        assert_eq!(Geometry::new(u32::MAX, 1), Err(SeatError::UnsupportedGeometry { row_bits: u32::MAX, column_bits: 1 }));
+    }
+
+    #[test]
+    fn round_trip() {
+        let geometry = Geometry::default();
+        for id in 0..geometry.seat_count() {
+            let pass = geometry.encode(Seat(id)).unwrap();
+            assert_eq!(geometry.decode(&pass), Ok(Seat(id)));
+        }
     }
 
     #[test]
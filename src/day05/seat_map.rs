@@ -0,0 +1,183 @@
+use std::fmt;
+use std::fmt::Write;
+use std::ops::Range;
+use super::{Geometry, Seat, SeatError};
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Cell {
+    Occupied,
+    Free,
+    /// Before the first or after the last occupied seat, these don't exist on the plane.
+    Missing,
+}
+
+/// A run of free seats next to each other in a single row.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FreeBlock {
+    pub row: u32,
+    pub columns: Range<u32>,
+}
+
+impl FreeBlock {
+    pub fn len(&self) -> u32 {
+        self.columns.end - self.columns.start
+    }
+}
+
+/// Only the occupied seats are stored, so the size of the plane doesn't matter.
+pub struct SeatMap {
+    geometry: Geometry,
+    /// Sorted and without duplicates.
+    occupied: Vec<u32>,
+    /// From the first occupied seat to one past the last.
+    bounds: Range<u32>,
+}
+
+impl SeatMap {
+    pub fn new(geometry: Geometry, seats: &[Seat]) -> Result<Self, SeatError> {
+        if let Some(seat) = seats.iter().find(|seat| seat.0 >= geometry.seat_count()) {
+            return Err(SeatError::OutOfRange(seat.0));
+        }
+        let mut occupied: Vec<u32> = seats.iter().map(|seat| seat.0).collect();
+        occupied.sort_unstable();
+        occupied.dedup();
+
+        let bounds = match (occupied.first(), occupied.last()) {
+            (Some(first), Some(last)) => *first..*last + 1,
+            _ => 0..0,
+        };
+        Ok(Self { geometry, occupied, bounds })
+    }
+
+    pub fn cell(&self, seat: Seat) -> Cell {
+        if !self.bounds.contains(&seat.0) {
+            Cell::Missing
+        } else if self.occupied.binary_search(&seat.0).is_ok() {
+            Cell::Occupied
+        } else {
+            Cell::Free
+        }
+    }
+
+    /// Runs of free seats between neighbouring occupied seats, in order.
+    fn free_runs(&self) -> impl Iterator<Item=Range<u32>> + '_ {
+        self.occupied.windows(2)
+            .map(|w| w[0] + 1..w[1])
+            .filter(|run| !run.is_empty())
+    }
+
+    /// Every free seat between the first and last occupied seat.
+    pub fn gaps(&self) -> Vec<Seat> {
+        self.free_runs().flatten().map(Seat).collect()
+    }
+
+    pub fn free_blocks(&self) -> Vec<FreeBlock> {
+        let columns = self.geometry.columns();
+        let mut blocks = Vec::new();
+
+        for run in self.free_runs() {
+            let mut start = run.start;
+            while start < run.end {
+                let row = start / columns;
+                let end = run.end.min((row + 1) * columns);
+                blocks.push(FreeBlock { row, columns: start - row * columns..end - row * columns });
+                start = end;
+            }
+        }
+        blocks
+    }
+
+    /// Free blocks that can seat a group of `size`, largest first.
+    pub fn blocks_for_group(&self, size: u32) -> Vec<FreeBlock> {
+        let mut blocks: Vec<FreeBlock> = self.free_blocks().into_iter()
+            .filter(|b| b.len() >= size)
+            .collect();
+        blocks.sort_by_key(|b| std::cmp::Reverse(b.len()));
+        blocks
+    }
+}
+
+/// One line per row, `#` for occupied, `.` for free and `-` for missing seats.
+impl fmt::Display for SeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self.geometry.columns();
+        for row in 0..self.geometry.rows() {
+            for column in 0..columns {
+                f.write_char(match self.cell(Seat(row * columns + column)) {
+                    Cell::Occupied => '#',
+                    Cell::Free => '.',
+                    Cell::Missing => '-',
+                })?;
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::read_file;
+
+    fn small_map() -> SeatMap {
+        let seats: Vec<Seat> = vec![3, 4, 5, 7, 8, 9, 13, 14].into_iter().map(Seat).collect();
+        SeatMap::new(Geometry::new(2, 2).unwrap(), &seats).unwrap()
+    }
+
+    #[test]
+    fn render() {
+        assert_eq!(small_map().to_string(), "---#\n##.#\n##..\n.##-\n");
+    }
+
+    #[test]
+    fn gaps_and_blocks() {
+        let map = small_map();
+        assert_eq!(map.gaps(), vec![Seat(6), Seat(10), Seat(11), Seat(12)]);
+        assert_eq!(map.free_blocks(), vec![
+            FreeBlock { row: 1, columns: 2..3 },
+            FreeBlock { row: 2, columns: 2..4 },
+            FreeBlock { row: 3, columns: 0..1 },
+        ]);
+        assert_eq!(map.blocks_for_group(2), vec![FreeBlock { row: 2, columns: 2..4 }]);
+    }
+
+    #[test]
+    fn out_of_range_seat() {
+        assert!(SeatMap::new(Geometry::new(2, 2).unwrap(), &[Seat(16)]).is_err());
+    }
+
+    #[test]
+    fn empty_plane() {
+        let map = SeatMap::new(Geometry::new(1, 1).unwrap(), &[]).unwrap();
+        assert_eq!(map.to_string(), "--\n--\n");
+        assert!(map.gaps().is_empty());
+    }
+
+    #[test]
+    fn large_plane() {
+        let geometry = Geometry::new(28, 3).unwrap();
+        let map = SeatMap::new(geometry, &[Seat(5), Seat(30), Seat(12), Seat(5)]).unwrap();
+        assert_eq!(map.cell(Seat(4)), Cell::Missing);
+        assert_eq!(map.cell(Seat(12)), Cell::Occupied);
+        assert_eq!(map.cell(Seat(20)), Cell::Free);
+        assert_eq!(map.cell(Seat(1 << 30)), Cell::Missing);
+        assert_eq!(map.free_blocks(), vec![
+            FreeBlock { row: 0, columns: 6..8 },
+            FreeBlock { row: 1, columns: 0..4 },
+            FreeBlock { row: 1, columns: 5..8 },
+            FreeBlock { row: 2, columns: 0..8 },
+            FreeBlock { row: 3, columns: 0..6 },
+        ]);
+        assert_eq!(map.blocks_for_group(8), vec![FreeBlock { row: 2, columns: 0..8 }]);
+        assert_eq!(map.gaps().len(), 23);
+    }
+
+    #[test]
+    fn part_2() {
+        let seats: Vec<Seat> = read_file("./src/day05/input.txt").unwrap();
+        let map = SeatMap::new(Geometry::default(), &seats).unwrap();
+        println!("{}", map);
+        assert_eq!(map.gaps(), vec![Seat(727)]);
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum AnswerError {
+    /// The character is not part of the alphabet.
+    Unknown(char),
+    /// Whitespace and control characters can't be questions.
+    Invalid(char),
+    Duplicate(char),
+}
+
+impl fmt::Display for AnswerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnswerError::Unknown(c) => write!(f, "Unknown question {:?}", c),
+            AnswerError::Invalid(c) => write!(f, "Invalid question {:?}", c),
+            AnswerError::Duplicate(c) => write!(f, "Duplicate question {:?} in alphabet", c),
+        }
+    }
+}
+
+pub trait QuestionSet: Clone + Eq {
+    fn union(&self, other: &Self) -> Self;
+    fn intersection(&self, other: &Self) -> Self;
+    fn difference(&self, other: &Self) -> Self;
+    fn symmetric_difference(&self, other: &Self) -> Self;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Parses answers into question sets and maps them back to characters.
+pub trait Alphabet {
+    type Set: QuestionSet;
+
+    fn parse(&self, answers: &str) -> Result<Self::Set, AnswerError>;
+    fn questions(&self, set: &Self::Set) -> Vec<char>;
+}
+
+/// A set of any width, trailing empty words are trimmed so equal sets compare equal.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct BitSet(Vec<u64>);
+
+impl BitSet {
+    pub fn insert(&mut self, i: usize) {
+        let word = i / 64;
+        if word >= self.0.len() {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << (i % 64);
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.0.get(i / 64).is_some_and(|w| w & (1 << (i % 64)) != 0)
+    }
+
+    pub fn indices(&self) -> impl Iterator<Item=usize> + '_ {
+        (0..self.0.len() * 64).filter(move |i| self.contains(*i))
+    }
+
+    fn combine<F: Fn(u64, u64) -> u64>(&self, other: &Self, f: F) -> Self {
+        let n = self.0.len().max(other.0.len());
+        let mut words: Vec<u64> = (0..n)
+            .map(|i| f(self.0.get(i).copied().unwrap_or(0), other.0.get(i).copied().unwrap_or(0)))
+            .collect();
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        BitSet(words)
+    }
+}
+
+impl QuestionSet for BitSet {
+    fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b)
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+/// Fallback for when the questions can't be mapped to bits up front.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CharSet(HashSet<char>);
+
+impl QuestionSet for CharSet {
+    fn union(&self, other: &Self) -> Self {
+        CharSet(self.0.union(&other.0).copied().collect())
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        CharSet(self.0.intersection(&other.0).copied().collect())
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        CharSet(self.0.difference(&other.0).copied().collect())
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self {
+        CharSet(self.0.symmetric_difference(&other.0).copied().collect())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A known alphabet, questions are stored as bits in the order they appear in the alphabet.
+pub struct Letters {
+    chars: Vec<char>,
+    index: HashMap<char, usize>,
+}
+
+impl Letters {
+    pub fn new(alphabet: &str) -> Result<Self, AnswerError> {
+        let mut chars = Vec::new();
+        let mut index = HashMap::new();
+        for c in alphabet.chars() {
+            if c.is_whitespace() || c.is_control() {
+                return Err(AnswerError::Invalid(c));
+            }
+            if index.insert(c, chars.len()).is_some() {
+                return Err(AnswerError::Duplicate(c));
+            }
+            chars.push(c);
+        }
+        Ok(Self { chars, index })
+    }
+
+    pub fn lowercase() -> Self {
+        Letters::new("abcdefghijklmnopqrstuvwxyz").unwrap()
+    }
+}
+
+impl Alphabet for Letters {
+    type Set = BitSet;
+
+    fn parse(&self, answers: &str) -> Result<BitSet, AnswerError> {
+        let mut set = BitSet::default();
+        for c in answers.chars() {
+            match self.index.get(&c) {
+                Some(i) => set.insert(*i),
+                None => return Err(AnswerError::Unknown(c)),
+            }
+        }
+        Ok(set)
+    }
+
+    fn questions(&self, set: &BitSet) -> Vec<char> {
+        set.indices().map(|i| self.chars[i]).collect()
+    }
+}
+
+/// Accepts any printable character as a question.
+pub struct AnyChar;
+
+impl Alphabet for AnyChar {
+    type Set = CharSet;
+
+    fn parse(&self, answers: &str) -> Result<CharSet, AnswerError> {
+        answers.chars()
+            .map(|c| if c.is_whitespace() || c.is_control() {
+                Err(AnswerError::Invalid(c))
+            } else {
+                Ok(c)
+            })
+            .collect::<Result<_, _>>()
+            .map(CharSet)
+    }
+
+    fn questions(&self, set: &CharSet) -> Vec<char> {
+        let mut questions: Vec<char> = set.0.iter().copied().collect();
+        questions.sort_unstable();
+        questions
+    }
+}
+
+pub fn parse_group<A: Alphabet, T: AsRef<str>>(alphabet: &A, record: &[T]) -> Result<Vec<A::Set>, AnswerError> {
+    record.iter().map(|answers| alphabet.parse(answers.as_ref())).collect()
+}
+
+/// Questions anyone in the group answered.
+pub fn any_answered<S: QuestionSet>(group: &[S]) -> Option<S> {
+    group.iter().cloned().reduce(|a, b| a.union(&b))
+}
+
+/// Questions everyone in the group answered.
+pub fn all_answered<S: QuestionSet>(group: &[S]) -> Option<S> {
+    group.iter().cloned().reduce(|a, b| a.intersection(&b))
+}
+
+pub fn count_any<A: Alphabet, T: AsRef<str>>(alphabet: &A, records: &[Vec<T>]) -> Result<usize, AnswerError> {
+    let mut sum = 0;
+    for record in records {
+        let group = parse_group(alphabet, record)?;
+        sum += any_answered(&group).map_or(0, |set| set.len());
+    }
+    Ok(sum)
+}
+
+pub fn count_all<A: Alphabet, T: AsRef<str>>(alphabet: &A, records: &[Vec<T>]) -> Result<usize, AnswerError> {
+    let mut sum = 0;
+    for record in records {
+        let group = parse_group(alphabet, record)?;
+        sum += all_answered(&group).map_or(0, |set| set.len());
+    }
+    Ok(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::read_records;
+
+    #[test]
+    fn set_operations() {
+        let letters = Letters::lowercase();
+        let a = letters.parse("abcx").unwrap();
+        let b = letters.parse("bcdz").unwrap();
+
+        assert_eq!(letters.questions(&a.union(&b)), vec!['a', 'b', 'c', 'd', 'x', 'z']);
+        assert_eq!(letters.questions(&a.intersection(&b)), vec!['b', 'c']);
+        assert_eq!(letters.questions(&a.difference(&b)), vec!['a', 'x']);
+        assert_eq!(letters.questions(&a.symmetric_difference(&b)), vec!['a', 'd', 'x', 'z']);
+        assert!(a.difference(&a).is_empty());
+        assert_eq!(a.difference(&a), BitSet::default());
+    }
+
+    #[test]
+    fn wide_alphabet() {
+        let alphabet: String = (0..100).map(|i| char::from_u32(0x100 + i).unwrap()).collect();
+        let letters = Letters::new(&alphabet).unwrap();
+        let last = alphabet.chars().last().unwrap();
+        let set = letters.parse(&format!("{}Ā", last)).unwrap();
+
+        assert_eq!(set.len(), 2);
+        assert_eq!(letters.questions(&set), vec!['Ā', last]);
+    }
+
+    #[test]
+    fn any_char() {
+        let a = AnyChar.parse("aé1").unwrap();
+        let b = AnyChar.parse("é2").unwrap();
+
+        assert_eq!(AnyChar.questions(&a.intersection(&b)), vec!['é']);
+        assert_eq!(AnyChar.questions(&a.symmetric_difference(&b)), vec!['1', '2', 'a']);
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(Letters::lowercase().parse("abC"), Err(AnswerError::Unknown('C')));
+        assert_eq!(Letters::lowercase().parse("a b"), Err(AnswerError::Unknown(' ')));
+        assert_eq!(AnyChar.parse("a\tb"), Err(AnswerError::Invalid('\t')));
+        assert_eq!(Letters::new("abca").err(), Some(AnswerError::Duplicate('a')));
+    }
+
+    #[test]
+    fn count_test_file() {
+        let records = read_records("./src/day06/input_test.txt").unwrap();
+
+        assert_eq!(count_any(&Letters::lowercase(), &records), Ok(11));
+        assert_eq!(count_all(&Letters::lowercase(), &records), Ok(6));
+        assert_eq!(count_any(&AnyChar, &records), Ok(11));
+        assert_eq!(count_all(&AnyChar, &records), Ok(6));
+    }
+
+    #[test]
+    fn count_input_file() {
+        let records = read_records("./src/day06/input.txt").unwrap();
+
+        assert_eq!(count_any(&Letters::lowercase(), &records), Ok(6911));
+        assert_eq!(count_all(&Letters::lowercase(), &records), Ok(3473));
+        assert_eq!(count_any(&AnyChar, &records), Ok(6911));
+        assert_eq!(count_all(&AnyChar, &records), Ok(3473));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use serde_json::{json, Value};
+use super::questions::{Alphabet, AnswerError, parse_group};
+
+/// How many people answered each question.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Stats {
+    pub people: usize,
+    pub answers: BTreeMap<char, usize>,
+}
+
+impl Stats {
+    fn add(&mut self, other: &Stats) {
+        self.people += other.people;
+        for (question, count) in &other.answers {
+            *self.answers.entry(*question).or_insert(0) += count;
+        }
+    }
+
+    /// Questions answered by everyone.
+    pub fn unanimous(&self) -> Vec<char> {
+        self.answers.iter()
+            .filter(|(_, count)| **count == self.people)
+            .map(|(question, _)| *question)
+            .collect()
+    }
+
+    /// Questions answered by more than half.
+    pub fn majority(&self) -> Vec<char> {
+        self.answers.iter()
+            .filter(|(_, count)| **count * 2 > self.people)
+            .map(|(question, _)| *question)
+            .collect()
+    }
+
+    fn to_json(&self) -> Value {
+        let answers: BTreeMap<String, usize> = self.answers.iter()
+            .map(|(question, count)| (question.to_string(), *count))
+            .collect();
+        json!({
+            "people": self.people,
+            "answers": answers,
+            "unanimous": self.unanimous().iter().collect::<String>(),
+            "majority": self.majority().iter().collect::<String>(),
+        })
+    }
+}
+
+pub struct Report {
+    pub groups: Vec<Stats>,
+    pub total: Stats,
+    /// Number of groups of each size.
+    pub group_sizes: BTreeMap<usize, usize>,
+}
+
+impl Report {
+    pub fn new<A: Alphabet, T: AsRef<str>>(alphabet: &A, records: &[Vec<T>]) -> Result<Self, AnswerError> {
+        let mut groups = Vec::with_capacity(records.len());
+        let mut total = Stats::default();
+        let mut group_sizes = BTreeMap::new();
+
+        for record in records {
+            let mut stats = Stats { people: record.len(), answers: BTreeMap::new() };
+            for set in parse_group(alphabet, record)? {
+                for question in alphabet.questions(&set) {
+                    *stats.answers.entry(question).or_insert(0) += 1;
+                }
+            }
+            total.add(&stats);
+            *group_sizes.entry(stats.people).or_insert(0) += 1;
+            groups.push(stats);
+        }
+
+        Ok(Self { groups, total, group_sizes })
+    }
+
+    /// For each question, the number of groups where everyone answered it.
+    pub fn unanimous_groups(&self) -> BTreeMap<char, usize> {
+        let mut out = BTreeMap::new();
+        for group in &self.groups {
+            for question in group.unanimous() {
+                *out.entry(question).or_insert(0) += 1;
+            }
+        }
+        out
+    }
+
+    pub fn to_table(&self) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "{:>5} {:>6}  {:<26} majority", "group", "people", "unanimous").unwrap();
+        for (i, group) in self.groups.iter().enumerate() {
+            write_stats_row(&mut out, &(i + 1).to_string(), group);
+        }
+        write_stats_row(&mut out, "all", &self.total);
+
+        let unanimous_groups = self.unanimous_groups();
+        writeln!(out).unwrap();
+        writeln!(out, "{:>8} {:>8} {:>9}", "question", "answered", "unanimous").unwrap();
+        for (question, count) in &self.total.answers {
+            writeln!(out, "{:>8} {:>8} {:>9}",
+                     question, count, unanimous_groups.get(question).unwrap_or(&0)).unwrap();
+        }
+
+        writeln!(out).unwrap();
+        writeln!(out, "{:>4} {:>6}", "size", "groups").unwrap();
+        for (size, count) in &self.group_sizes {
+            writeln!(out, "{:>4} {:>6}", size, count).unwrap();
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let group_sizes: BTreeMap<String, usize> = self.group_sizes.iter()
+            .map(|(size, count)| (size.to_string(), *count))
+            .collect();
+        let unanimous_groups: BTreeMap<String, usize> = self.unanimous_groups().iter()
+            .map(|(question, count)| (question.to_string(), *count))
+            .collect();
+        json!({
+            "groups": self.groups.iter().map(|g| g.to_json()).collect::<Vec<_>>(),
+            "total": self.total.to_json(),
+            "unanimous_groups": unanimous_groups,
+            "group_sizes": group_sizes,
+        }).to_string()
+    }
+}
+
+fn write_stats_row(out: &mut String, label: &str, stats: &Stats) {
+    let row = format!("{:>5} {:>6}  {:<26} {}",
+                      label,
+                      stats.people,
+                      stats.unanimous().iter().collect::<String>(),
+                      stats.majority().iter().collect::<String>());
+    writeln!(out, "{}", row.trim_end()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day06::questions::{Letters, count_all};
+    use crate::util::read_records;
+
+    fn test_report() -> Report {
+        let records = read_records("./src/day06/input_test.txt").unwrap();
+        Report::new(&Letters::lowercase(), &records).unwrap()
+    }
+
+    #[test]
+    fn group_stats() {
+        let report = test_report();
+        let group = &report.groups[2];
+
+        assert_eq!(group.people, 2);
+        assert_eq!(group.answers, vec![('a', 2), ('b', 1), ('c', 1)].into_iter().collect());
+        assert_eq!(group.unanimous(), vec!['a']);
+        assert_eq!(group.majority(), vec!['a']);
+    }
+
+    #[test]
+    fn totals() {
+        let report = test_report();
+
+        assert_eq!(report.total.people, 11);
+        assert_eq!(report.total.answers, vec![('a', 8), ('b', 4), ('c', 3)].into_iter().collect());
+        assert_eq!(report.total.majority(), vec!['a']);
+        assert_eq!(report.unanimous_groups(), vec![('a', 3), ('b', 2), ('c', 1)].into_iter().collect());
+        assert_eq!(report.group_sizes, vec![(1, 2), (2, 1), (3, 1), (4, 1)].into_iter().collect());
+
+        let unanimous: usize = report.groups.iter().map(|g| g.unanimous().len()).sum();
+        let records = read_records("./src/day06/input_test.txt").unwrap();
+        assert_eq!(Ok(unanimous), count_all(&Letters::lowercase(), &records));
+    }
+
+    #[test]
+    fn table() {
+        let table = test_report().to_table();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines[3], "    3      2  a                          a");
+        assert_eq!(lines[6], "  all     11                             a");
+        assert!(table.contains("       a        8         3\n"));
+        assert!(table.ends_with("   4      1\n"));
+    }
+
+    #[test]
+    fn json() {
+        let json: Value = serde_json::from_str(&test_report().to_json()).unwrap();
+
+        assert_eq!(json["groups"][2], json!({
+            "people": 2,
+            "answers": {"a": 2, "b": 1, "c": 1},
+            "unanimous": "a",
+            "majority": "a",
+        }));
+        assert_eq!(json["total"]["people"], 11);
+        assert_eq!(json["group_sizes"]["1"], 2);
+    }
+
+    #[test]
+    fn input_report() {
+        let records = read_records("./src/day06/input.txt").unwrap();
+        let report = Report::new(&Letters::lowercase(), &records).unwrap();
+        println!("{}", report.to_table());
+    }
+}
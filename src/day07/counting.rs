@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use num_bigint::BigUint;
+use super::{Bag, BagGraph, Cycle};
+
+impl BagGraph {
+    /// The number of bags inside `bag`, or the cycle that makes it infinite. Each bag's
+    /// contents are only counted once, however many times it appears.
+    pub fn total_contents(&self, bag: &Bag) -> Result<BigUint, Cycle> {
+        if let Some(cycle) = self.find_cycle_from(bag) {
+            return Err(cycle);
+        }
+        Ok(self.count_contents(bag))
+    }
+
+    /// Counts the innermost bags first, so each bag's contents are already known when the
+    /// bags holding it are counted.
+    fn count_contents<'a>(&'a self, bag: &'a Bag) -> BigUint {
+        let mut order = Vec::new();
+        self.post_order(bag, &mut HashSet::new(), &mut order);
+
+        let mut counts: HashMap<&Bag, BigUint> = HashMap::new();
+        for outer in order {
+            let count = self.contents(outer).iter()
+                .map(|(n, b)| BigUint::from(*n) * (&counts[b] + 1u32))
+                .sum();
+            counts.insert(outer, count);
+        }
+        counts.remove(bag).unwrap_or_default()
+    }
+
+    /// How many of each bag end up inside `bag`.
+    pub fn contents_breakdown(&self, bag: &Bag) -> Result<BTreeMap<Bag, BigUint>, Cycle> {
+        if let Some(cycle) = self.find_cycle_from(bag) {
+            return Err(cycle);
+        }
+
+        // Outermost bags first, so every bag has all its containers counted before its own
+        // contents are.
+        let mut order = Vec::new();
+        self.post_order(bag, &mut HashSet::new(), &mut order);
+
+        let mut counts: HashMap<&Bag, BigUint> = HashMap::new();
+        counts.insert(bag, BigUint::from(1u32));
+        for outer in order.into_iter().rev() {
+            let outer_count = counts[outer].clone();
+            for (n, inner) in self.contents(outer) {
+                *counts.entry(inner).or_default() += &outer_count * *n;
+            }
+        }
+
+        counts.remove(bag);
+        Ok(counts.into_iter().map(|(b, n)| (b.clone(), n)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day07::{BagManifest, Line};
+
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn breakdown() {
+        let graph = BagGraph::from_file("./src/day07/input_test.txt").unwrap();
+        let breakdown = graph.contents_breakdown(&Bag::new("shiny gold bag")).unwrap();
+
+        assert_eq!(breakdown, vec![
+            (Bag::new("dark olive bag"), big(1)),
+            (Bag::new("dotted black bag"), big(16)),
+            (Bag::new("faded blue bag"), big(13)),
+            (Bag::new("vibrant plum bag"), big(2)),
+        ].into_iter().collect());
+    }
+
+    #[test]
+    fn breakdown_sums_to_total() {
+        let graph = BagGraph::from_file("./src/day07/input.txt").unwrap();
+        for bag in graph.bags() {
+            let breakdown = graph.contents_breakdown(bag).unwrap();
+            let sum: BigUint = breakdown.values().sum();
+            assert_eq!(sum, graph.total_contents(bag).unwrap());
+        }
+    }
+
+    #[test]
+    fn deep_nesting() {
+        // 60 levels of 1000 bags each, far more than fits in a u64 and too many paths to
+        // count one by one without memoisation.
+        let rules: Vec<String> = (0..60)
+            .map(|i| format!("level{} a bags contain 1000 level{} a bags, 1000 level{} b bags.", i, i + 1, i + 1))
+            .chain((0..60).map(|i| format!("level{} b bags contain 1000 level{} a bags.", i, i + 1)))
+            .chain(vec!["level60 a bags contain no other bags.".to_string()])
+            .collect();
+        let graph = BagGraph::new(rules.iter().map(|r| r.parse().unwrap()).collect());
+        let top = Bag::new("level0 a bags");
+
+        let total = graph.total_contents(&top).unwrap();
+        let breakdown = graph.contents_breakdown(&top).unwrap();
+
+        assert!(total > big(u64::MAX));
+        assert_eq!(breakdown.values().sum::<BigUint>(), total);
+        assert_eq!(breakdown[&Bag::new("level1 b bags")], big(1000));
+    }
+
+    #[test]
+    fn long_chain() {
+        let length = 200_000;
+        let bag = |i| Bag(format!("c{} x", i));
+        let graph = BagGraph::new((0..length)
+            .map(|i| Line(bag(i), BagManifest(vec![(1, bag(i + 1))])))
+            .collect());
+        let top = bag(0);
+
+        assert_eq!(graph.total_contents(&top), Ok(big(length)));
+        assert_eq!(graph.topological_order().unwrap().first(), Some(&top));
+    }
+
+    #[test]
+    fn cycle() {
+        let graph = BagGraph::new(vec!["shiny gold bags contain 1 shiny gold bag.".parse().unwrap()]);
+        assert!(graph.contents_breakdown(&Bag::new("shiny gold bag")).is_err());
+    }
+}
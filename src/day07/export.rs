@@ -0,0 +1,171 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use super::{Bag, BagGraph};
+
+pub enum Subgraph {
+    All,
+    /// The bag and everything inside it.
+    ReachableFrom(Bag),
+    /// The bag and everything it can end up inside.
+    ReachableTo(Bag),
+}
+
+pub struct ExportOptions {
+    subgraph: Subgraph,
+    highlight: Option<Bag>,
+}
+
+impl ExportOptions {
+    pub fn new() -> Self {
+        Self { subgraph: Subgraph::All, highlight: None }
+    }
+
+    pub fn with_subgraph(mut self, subgraph: Subgraph) -> Self {
+        self.subgraph = subgraph;
+        self
+    }
+
+    pub fn with_highlight(mut self, bag: Bag) -> Self {
+        self.highlight = Some(bag);
+        self
+    }
+}
+
+impl BagGraph {
+    fn subgraph_bags<'a>(&'a self, subgraph: &'a Subgraph) -> BTreeSet<&'a Bag> {
+        match subgraph {
+            Subgraph::All => self.bags(),
+            Subgraph::ReachableFrom(bag) => {
+                let mut bags = self.contents_of(bag);
+                bags.insert(bag);
+                bags
+            }
+            Subgraph::ReachableTo(bag) => {
+                let mut bags = self.containers_of(bag);
+                bags.insert(bag);
+                bags
+            }
+        }
+    }
+
+    fn subgraph_edges<'a>(&'a self, bags: &BTreeSet<&'a Bag>) -> Vec<(&'a Bag, u32, &'a Bag)> {
+        bags.iter()
+            .flat_map(|outer| self.contents(outer).iter().map(move |(n, inner)| (*outer, *n, inner)))
+            .filter(|(_, _, inner)| bags.contains(inner))
+            .collect()
+    }
+
+    pub fn to_dot(&self, options: &ExportOptions) -> String {
+        let bags = self.subgraph_bags(&options.subgraph);
+        let mut out = String::from("digraph bags {\n");
+
+        for bag in &bags {
+            if options.highlight.as_ref() == Some(*bag) {
+                writeln!(out, "    {} [style=filled, fillcolor=gold];", dot_id(bag)).unwrap();
+            } else {
+                writeln!(out, "    {};", dot_id(bag)).unwrap();
+            }
+        }
+        for (outer, n, inner) in self.subgraph_edges(&bags) {
+            writeln!(out, "    {} -> {} [label=\"{}\"];", dot_id(outer), dot_id(inner), n).unwrap();
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid ids can't contain spaces, so nodes are numbered and labelled with their colour.
+    pub fn to_mermaid(&self, options: &ExportOptions) -> String {
+        let bags = self.subgraph_bags(&options.subgraph);
+        let id = |bag: &Bag| format!("n{}", bags.iter().position(|b| *b == bag).unwrap());
+        let mut out = String::from("graph LR\n");
+
+        for bag in &bags {
+            writeln!(out, "    {}[\"{}\"]", id(bag), bag.0.replace('"', "#quot;")).unwrap();
+        }
+        for (outer, n, inner) in self.subgraph_edges(&bags) {
+            writeln!(out, "    {} -->|{}| {}", id(outer), n, id(inner)).unwrap();
+        }
+        if let Some(bag) = options.highlight.as_ref().filter(|b| bags.contains(b)) {
+            writeln!(out, "    style {} fill:#ffd700,stroke:#333,stroke-width:2px", id(bag)).unwrap();
+        }
+
+        out
+    }
+}
+
+fn dot_id(bag: &Bag) -> String {
+    format!("\"{}\"", bag.0.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shiny_gold() -> Bag {
+        Bag::new("shiny gold bag")
+    }
+
+    #[test]
+    fn dot_reachable_from() {
+        let graph = BagGraph::from_file("./src/day07/input_test.txt").unwrap();
+        let options = ExportOptions::new()
+            .with_subgraph(Subgraph::ReachableFrom(shiny_gold()))
+            .with_highlight(shiny_gold());
+
+        assert_eq!(graph.to_dot(&options), r#"digraph bags {
+    "dark olive";
+    "dotted black";
+    "faded blue";
+    "shiny gold" [style=filled, fillcolor=gold];
+    "vibrant plum";
+    "dark olive" -> "faded blue" [label="3"];
+    "dark olive" -> "dotted black" [label="4"];
+    "shiny gold" -> "dark olive" [label="1"];
+    "shiny gold" -> "vibrant plum" [label="2"];
+    "vibrant plum" -> "faded blue" [label="5"];
+    "vibrant plum" -> "dotted black" [label="6"];
+}
+"#);
+    }
+
+    #[test]
+    fn mermaid_reachable_to() {
+        let graph = BagGraph::from_file("./src/day07/input_test.txt").unwrap();
+        let options = ExportOptions::new()
+            .with_subgraph(Subgraph::ReachableTo(shiny_gold()))
+            .with_highlight(shiny_gold());
+
+        assert_eq!(graph.to_mermaid(&options), r#"graph LR
+    n0["bright white"]
+    n1["dark orange"]
+    n2["light red"]
+    n3["muted yellow"]
+    n4["shiny gold"]
+    n0 -->|1| n4
+    n1 -->|3| n0
+    n1 -->|4| n3
+    n2 -->|1| n0
+    n2 -->|2| n3
+    n3 -->|2| n4
+    style n4 fill:#ffd700,stroke:#333,stroke-width:2px
+"#);
+    }
+
+    #[test]
+    fn whole_graph() {
+        let graph = BagGraph::from_file("./src/day07/input.txt").unwrap();
+        let dot = graph.to_dot(&ExportOptions::new());
+        let mermaid = graph.to_mermaid(&ExportOptions::new());
+
+        let edges: usize = graph.bags().iter().map(|b| graph.contents(b).len()).sum();
+        assert_eq!(dot.matches(" -> ").count(), edges);
+        assert_eq!(mermaid.matches(" -->|").count(), edges);
+        assert!(!mermaid.contains("style"));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(dot_id(&Bag("a \"quoted\\\" bag".to_string())), r#""a \"quoted\\\" bag""#);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use super::{Bag, BagManifest, Line};
+
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for BagManifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("no other bags");
+        }
+        for (i, (n, bag)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} {} {}", n, bag, if *n == 1 { "bag" } else { "bags" })?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bags contain {}.", self.0, self.1)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum FormatError {
+    /// Two rules for the same bag that say different things.
+    ConflictingRules(Line, Line),
+    /// A rule whose repeated bags add up to more than a `u32`.
+    CountOverflow(Line),
+}
+
+impl BagManifest {
+    /// Sorted by colour, with repeated bags added together and empty entries removed,
+    /// `None` if a total doesn't fit in a `u32`.
+    fn normalised(&self) -> Option<BagManifest> {
+        let mut counts: BTreeMap<&Bag, u32> = BTreeMap::new();
+        for (n, bag) in &self.0 {
+            let count = counts.entry(bag).or_insert(0);
+            *count = count.checked_add(*n)?;
+        }
+        Some(BagManifest(counts.into_iter()
+            .filter(|(_, n)| *n > 0)
+            .map(|(bag, n)| (n, bag.clone()))
+            .collect()))
+    }
+}
+
+/// Sorts the rules by bag and removes duplicates, failing if a bag has rules that disagree.
+pub fn normalise(lines: Vec<Line>) -> Result<Vec<Line>, FormatError> {
+    let mut rules: BTreeMap<Bag, Line> = BTreeMap::new();
+    for Line(bag, manifest) in lines {
+        let normalised = match manifest.normalised() {
+            Some(normalised) => normalised,
+            None => return Err(FormatError::CountOverflow(Line(bag, manifest))),
+        };
+        let line = Line(bag.clone(), normalised);
+        match rules.get(&bag) {
+            Some(existing) if *existing != line => return Err(FormatError::ConflictingRules(existing.clone(), line)),
+            Some(_) => {}
+            None => {
+                rules.insert(bag, line);
+            }
+        }
+    }
+    Ok(rules.into_values().collect())
+}
+
+/// One rule per line, in the same format as the puzzle input.
+pub fn to_rules_text(lines: &[Line]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::read_file;
+    use quickcheck::{quickcheck, Arbitrary, Gen};
+
+    fn line(s: &str) -> Line {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn format_rules() {
+        for rule in &[
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.",
+            "bright white bags contain 1 shiny gold bag.",
+            "faded blue bags contain no other bags.",
+        ] {
+            assert_eq!(line(rule).to_string(), *rule);
+        }
+    }
+
+    #[test]
+    fn input_round_trip() {
+        let lines: Vec<Line> = read_file("./src/day07/input.txt").unwrap();
+        let text = to_rules_text(&lines);
+        let reparsed: Vec<Line> = text.lines().map(line).collect();
+
+        assert_eq!(reparsed, lines);
+        assert_eq!(text.trim_end(), std::fs::read_to_string("./src/day07/input.txt").unwrap().trim_end());
+    }
+
+    #[test]
+    fn normalise_rules() {
+        let lines = vec![
+            line("shiny gold bags contain 2 faded blue bags, 1 dark olive bag, 1 faded blue bag."),
+            line("faded blue bags contain no other bags."),
+            line("shiny gold bags contain 1 dark olive bag, 3 faded blue bags."),
+            line("dark olive bags contain 0 faded blue bags."),
+        ];
+
+        assert_eq!(to_rules_text(&normalise(lines).unwrap()), "\
+dark olive bags contain no other bags.
+faded blue bags contain no other bags.
+shiny gold bags contain 1 dark olive bag, 3 faded blue bags.
+");
+    }
+
+    #[test]
+    fn conflicting_rules() {
+        let lines = vec![
+            line("shiny gold bags contain 1 dark olive bag."),
+            line("shiny gold bags contain 2 dark olive bags."),
+        ];
+
+        assert_eq!(normalise(lines), Err(FormatError::ConflictingRules(
+            line("shiny gold bags contain 1 dark olive bag."),
+            line("shiny gold bags contain 2 dark olive bags."),
+        )));
+    }
+
+    #[test]
+    fn count_overflow() {
+        let rule = "shiny gold bags contain 4294967295 dark olive bags, 1 dark olive bag.";
+        assert_eq!(normalise(vec![line(rule)]), Err(FormatError::CountOverflow(line(rule))));
+    }
+
+    #[derive(Clone, Debug)]
+    struct Rules(Vec<Line>);
+
+    fn arbitrary_bag(g: &mut Gen) -> Bag {
+        let adjectives = ["light", "dark", "shiny", "faded", "muted", "dotted", "vibrant"];
+        let colours = ["red", "gold", "blue", "olive", "plum", "black", "white", "yellow"];
+        Bag(format!("{} {}", g.choose(&adjectives).unwrap(), g.choose(&colours).unwrap()))
+    }
+
+    impl Arbitrary for Rules {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let count = usize::arbitrary(g) % 20;
+            let lines = (0..count)
+                .map(|_| {
+                    let contents = (0..usize::arbitrary(g) % 4)
+                        .map(|_| (u32::arbitrary(g) % 12, arbitrary_bag(g)))
+                        .collect();
+                    Line(arbitrary_bag(g), BagManifest(contents))
+                })
+                .collect();
+            Rules(lines)
+        }
+    }
+
+    #[test]
+    fn round_trip_property() {
+        fn round_trip(rules: Rules) -> bool {
+            let reparsed: Vec<Line> = to_rules_text(&rules.0).lines().map(line).collect();
+            reparsed == rules.0
+        }
+        quickcheck(round_trip as fn(Rules) -> bool);
+    }
+
+    #[test]
+    fn normalise_property() {
+        fn normalise_is_stable(rules: Rules) -> bool {
+            match normalise(rules.0) {
+                Ok(normalised) => {
+                    let reparsed: Vec<Line> = to_rules_text(&normalised).lines().map(line).collect();
+                    normalise(reparsed.clone()) == Ok(normalised.clone()) && reparsed == normalised
+                }
+                Err(FormatError::ConflictingRules(a, b)) => a.0 == b.0 && a != b,
+                Err(FormatError::CountOverflow(_)) => false,
+            }
+        }
+        quickcheck(normalise_is_stable as fn(Rules) -> bool);
+    }
+}
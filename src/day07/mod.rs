@@ -1,14 +1,26 @@
 use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
 use std::str::FromStr;
 use lazy_static::lazy_static;
 
+mod counting;
+mod export;
+mod format;
+mod query;
+
+pub use query::run as run_query;
+
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 struct Bag(String);
 
 impl Bag {
     fn new(s: &str) -> Self {
-        let captures = BAG_REGEX.captures(s).unwrap();
-        Bag(captures[1].to_string())
+        Bag::parse(s).unwrap()
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        BAG_REGEX.captures(s).map(|captures| Bag(captures[1].to_string()))
     }
 }
 
@@ -16,23 +28,23 @@ impl Bag {
 struct BagManifest(Vec<(u32, Bag)>);
 
 impl BagManifest {
-    fn new(s: &str) -> Self {
+    fn new(s: &str) -> Option<Self> {
         if s == "no other bags" {
-            return BagManifest(Vec::new());
+            return Some(BagManifest(Vec::new()));
         }
         let mut v = Vec::new();
         let parts = s.split(", ");
         for p in parts {
-            let captures = MANIFEST_REGEX.captures(p).unwrap();
-            let n = captures[1].parse().unwrap();
-            v.push((n, Bag::new(&captures[2])));
+            let captures = MANIFEST_REGEX.captures(p)?;
+            let n = captures[1].parse().ok()?;
+            v.push((n, Bag::parse(&captures[2])?));
         }
 
-        BagManifest(v)
+        Some(BagManifest(v))
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 struct Line(Bag, BagManifest);
 
 
@@ -44,21 +56,187 @@ lazy_static! {
 }
 
 impl FromStr for Line {
-    type Err = ();
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let captures = LINE_REGEX.captures(s).unwrap();
-        let bag = Bag::new(&captures[1]);
-        let manifest = BagManifest::new(&captures[2]);
+        let invalid = || format!("Invalid rule: {}", s);
+        let captures = LINE_REGEX.captures(s).ok_or_else(invalid)?;
+        let bag = Bag::parse(&captures[1]).ok_or_else(invalid)?;
+        let manifest = BagManifest::new(&captures[2]).ok_or_else(invalid)?;
         Ok(Line(bag, manifest))
     }
 }
 
+/// A rule set that contains itself, starting and ending with the same bag.
+#[derive(Debug, Eq, PartialEq)]
+struct Cycle(Vec<Bag>);
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+/// The bag rules with edges in both directions, `contents` maps a bag to the bags directly
+/// inside it and `containers` maps a bag to the bags it is directly inside of.
+struct BagGraph {
+    contents: BTreeMap<Bag, Vec<(u32, Bag)>>,
+    containers: BTreeMap<Bag, Vec<(u32, Bag)>>,
+}
+
+impl BagGraph {
+    fn new(lines: Vec<Line>) -> Self {
+        let mut contents: BTreeMap<Bag, Vec<(u32, Bag)>> = BTreeMap::new();
+        let mut containers: BTreeMap<Bag, Vec<(u32, Bag)>> = BTreeMap::new();
+
+        for Line(bag, manifest) in lines {
+            for (n, inner) in &manifest.0 {
+                containers.entry(inner.clone()).or_default().push((*n, bag.clone()));
+            }
+            contents.entry(bag).or_default().extend(manifest.0);
+        }
+
+        Self { contents, containers }
+    }
+
+    fn from_file(filename: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let lines = source.lines()
+            .enumerate()
+            .map(|(i, line)| line.parse().map_err(|e| format!("Line {}: {}", i + 1, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(BagGraph::new(lines))
+    }
+
+    /// Every bag mentioned in the rules, including those without a rule of their own.
+    pub fn bags(&self) -> BTreeSet<&Bag> {
+        self.contents.keys().chain(self.containers.keys()).collect()
+    }
+
+    pub fn contents(&self, bag: &Bag) -> &[(u32, Bag)] {
+        self.contents.get(bag).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn direct_containers(&self, bag: &Bag) -> &[(u32, Bag)] {
+        self.containers.get(bag).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// All bags that eventually contain `bag`.
+    pub fn containers_of(&self, bag: &Bag) -> BTreeSet<&Bag> {
+        let mut containers = BTreeSet::new();
+        let mut candidates = vec![bag];
+
+        while let Some(next_bag) = candidates.pop() {
+            for (_, b) in self.direct_containers(next_bag) {
+                if containers.insert(b) {
+                    candidates.push(b);
+                }
+            }
+        }
+        containers
+    }
+
+    /// All bags that eventually end up inside `bag`.
+    pub fn contents_of(&self, bag: &Bag) -> BTreeSet<&Bag> {
+        let mut contents = BTreeSet::new();
+        let mut candidates = vec![bag];
+
+        while let Some(next_bag) = candidates.pop() {
+            for (_, b) in self.contents(next_bag) {
+                if contents.insert(b) {
+                    candidates.push(b);
+                }
+            }
+        }
+        contents
+    }
+
+    pub fn find_cycle(&self) -> Option<Cycle> {
+        let mut state = HashMap::new();
+        self.contents.keys()
+            .find_map(|bag| self.visit(bag, &mut state))
+    }
+
+    pub fn find_cycle_from(&self, bag: &Bag) -> Option<Cycle> {
+        self.visit(bag, &mut HashMap::new())
+    }
+
+    /// Depth-first search from `bag` with an explicit stack, so deeply nested rules can't
+    /// overflow the call stack. Each entry is a bag on the current path and how many of its
+    /// contents have been looked at.
+    fn visit<'a>(&'a self, bag: &'a Bag, state: &mut HashMap<&'a Bag, Visit>) -> Option<Cycle> {
+        if state.contains_key(bag) {
+            return None;
+        }
+        state.insert(bag, Visit::InProgress);
+        let mut stack = vec![(bag, 0)];
+
+        while let Some((current, next)) = stack.last_mut() {
+            let current: &'a Bag = current;
+            match self.contents(current).get(*next) {
+                Some((_, inner)) => {
+                    *next += 1;
+                    match state.get(inner) {
+                        Some(Visit::Done) => {}
+                        Some(Visit::InProgress) => {
+                            let start = stack.iter().position(|(b, _)| *b == inner).unwrap();
+                            let mut cycle: Vec<Bag> = stack[start..].iter().map(|(b, _)| (*b).clone()).collect();
+                            cycle.push(inner.clone());
+                            return Some(Cycle(cycle));
+                        }
+                        None => {
+                            state.insert(inner, Visit::InProgress);
+                            stack.push((inner, 0));
+                        }
+                    }
+                }
+                None => {
+                    state.insert(current, Visit::Done);
+                    stack.pop();
+                }
+            }
+        }
+        None
+    }
+
+    /// Appends `bag` and everything inside it to `order`, innermost bags first.
+    fn post_order<'a>(&'a self, bag: &'a Bag, visited: &mut HashSet<&'a Bag>, order: &mut Vec<&'a Bag>) {
+        if !visited.insert(bag) {
+            return;
+        }
+        let mut stack = vec![(bag, 0)];
+
+        while let Some((current, next)) = stack.last_mut() {
+            let current: &'a Bag = current;
+            match self.contents(current).get(*next) {
+                Some((_, inner)) => {
+                    *next += 1;
+                    if visited.insert(inner) {
+                        stack.push((inner, 0));
+                    }
+                }
+                None => {
+                    order.push(current);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    /// Every bag, each one after all the bags inside it.
+    fn post_order_all(&self) -> Vec<&Bag> {
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        for bag in self.bags() {
+            self.post_order(bag, &mut visited, &mut order);
+        }
+        order
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::util::read_file;
-    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn parse_bag() {
@@ -68,70 +246,101 @@ mod tests {
 
     #[test]
     fn parse_bag_manifest() {
-        assert_eq!(BagManifest::new("no other bags"), BagManifest(Vec::new()));
-        assert_eq!(BagManifest::new("1 shiny gold bag"), BagManifest(vec![(1, Bag::new("shiny gold bag"))]));
-        assert_eq!(BagManifest::new("2 shiny gold bags, 9 faded blue bags"), BagManifest(vec![
+        assert_eq!(BagManifest::new("no other bags"), Some(BagManifest(Vec::new())));
+        assert_eq!(BagManifest::new("1 shiny gold bag"), Some(BagManifest(vec![(1, Bag::new("shiny gold bag"))])));
+        assert_eq!(BagManifest::new("2 shiny gold bags, 9 faded blue bags"), Some(BagManifest(vec![
             (2, Bag::new("shiny gold bags")),
             (9, Bag::new("faded blue bags")),
-        ]));
+        ])));
+        assert_eq!(BagManifest::new("1 c"), None);
     }
 
     #[test]
-    fn part_1() {
-        let lines: Vec<Line> = read_file("./src/day07/input.txt").unwrap();
+    fn invalid_rules() {
+        assert_eq!("a b bags contain 1 c.".parse::<Line>(), Err("Invalid rule: a b bags contain 1 c.".to_string()));
+        assert!("light red bags contain".parse::<Line>().is_err());
+        assert!(BagGraph::from_file("./src/day07/missing.txt").is_err());
+    }
 
-        let nodes: HashMap<Bag, BagManifest> = lines.iter()
-            .map(|line| (line.0.clone(), line.1.clone()))
-            .collect();
+    fn graph(rules: &[&str]) -> BagGraph {
+        BagGraph::new(rules.iter().map(|r| r.parse().unwrap()).collect())
+    }
 
-        let mut contained_in: HashMap<Bag, Vec<Bag>> = HashMap::with_capacity(nodes.len());
+    #[test]
+    fn graph_edges() {
+        let graph = BagGraph::from_file("./src/day07/input_test.txt").unwrap();
+        let shiny_gold = Bag::new("shiny gold bag");
 
-        for (ref in_bag, ref manifest) in nodes {
-            for (_, ref bag) in &manifest.0 {
-                contained_in.entry(bag.clone()).or_insert_with(|| Vec::new()).push(in_bag.clone());
-            }
-        }
+        assert_eq!(graph.bags().len(), 9);
+        assert_eq!(graph.contents(&shiny_gold), &[
+            (1, Bag::new("dark olive bag")),
+            (2, Bag::new("vibrant plum bags")),
+        ]);
+        assert_eq!(graph.direct_containers(&shiny_gold), &[
+            (1, Bag::new("bright white bag")),
+            (2, Bag::new("muted yellow bags")),
+        ]);
+        assert_eq!(graph.contents(&Bag::new("unknown bag")), &[]);
+    }
 
-        let mut containers = HashSet::new();
-        let mut candidates = vec![Bag::new("shiny gold bag")];
+    #[test]
+    fn containers_and_contents() {
+        let graph = BagGraph::from_file("./src/day07/input_test.txt").unwrap();
+        let shiny_gold = Bag::new("shiny gold bag");
 
-        while !candidates.is_empty() {
-            let next_bag = candidates.pop().unwrap();
-            if let Some(c) = contained_in.get(&next_bag) {
-                for b in c {
-                    if !containers.contains(b) {
-                        containers.insert(b.clone());
-                        candidates.push(b.clone());
-                    }
-                }
-            }
-        }
+        assert_eq!(graph.containers_of(&shiny_gold).len(), 4);
+        assert_eq!(graph.contents_of(&shiny_gold).len(), 4);
+        assert_eq!(graph.total_contents(&shiny_gold), Ok(32u32.into()));
+        assert_eq!(graph.find_cycle(), None);
 
-        assert_eq!(containers.len(), 101);
+        let graph = BagGraph::from_file("./src/day07/input_test_2.txt").unwrap();
+        assert_eq!(graph.total_contents(&shiny_gold), Ok(126u32.into()));
     }
 
-    fn count_contained_bags(nodes: &HashMap<Bag, BagManifest>, bag: &Bag) -> u32 {
-        if let Some(contained) = nodes.get(bag) {
-            let sum = contained.0.iter()
-                .map(|(n, b)| n * (1+count_contained_bags(nodes, b)))
-                .sum();
-            sum
-        } else {
-            0
-        }
+    #[test]
+    fn detect_cycle() {
+        let graph = graph(&[
+            "light red bags contain 1 shiny gold bag.",
+            "shiny gold bags contain 2 dark red bags, 1 faded blue bag.",
+            "dark red bags contain 1 light red bag.",
+            "faded blue bags contain no other bags.",
+        ]);
+        let expected = Cycle(vec![
+            Bag::new("dark red bags"),
+            Bag::new("light red bags"),
+            Bag::new("shiny gold bags"),
+            Bag::new("dark red bags"),
+        ]);
+
+        assert_eq!(graph.find_cycle(), Some(expected));
+        assert!(graph.total_contents(&Bag::new("shiny gold bag")).is_err());
+        assert_eq!(graph.total_contents(&Bag::new("faded blue bag")), Ok(0u32.into()));
+        assert_eq!(graph.containers_of(&Bag::new("shiny gold bag")).len(), 3);
     }
 
     #[test]
-    fn part_2() {
-        let lines: Vec<Line> = read_file("./src/day07/input.txt").unwrap();
+    fn self_containing_bag() {
+        let graph = graph(&["shiny gold bags contain 1 shiny gold bag."]);
+        let shiny_gold = Bag::new("shiny gold bag");
 
-        let nodes: HashMap<Bag, BagManifest> = lines.iter()
-            .map(|line| (line.0.clone(), line.1.clone()))
-            .collect();
+        assert_eq!(graph.find_cycle(), Some(Cycle(vec![shiny_gold.clone(), shiny_gold.clone()])));
+        assert_eq!(graph.total_contents(&shiny_gold), Err(Cycle(vec![shiny_gold.clone(), shiny_gold])));
+    }
 
-        let contained = count_contained_bags(&nodes, &Bag::new("shiny gold bag"));
+    #[test]
+    fn part_1() {
+        let graph = BagGraph::from_file("./src/day07/input.txt").unwrap();
+        let containers = graph.containers_of(&Bag::new("shiny gold bag"));
 
-        println!("Contained: {}", contained);
+        assert_eq!(containers.len(), 101);
+    }
 
+    #[test]
+    fn part_2() {
+        let graph = BagGraph::from_file("./src/day07/input.txt").unwrap();
+        let contained = graph.total_contents(&Bag::new("shiny gold bag")).unwrap();
+
+        println!("Contained: {}", contained);
+        assert_eq!(contained, 108636u32.into());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::process;
+use std::str::FromStr;
+use num_bigint::BigUint;
+use super::{Bag, BagGraph, Cycle};
+
+/// A question about the rules, bags are named by colour and separated by commas:
+///
+/// ```text
+/// containers shiny gold         bags that can eventually hold shiny gold
+/// contents shiny gold           bags that end up inside shiny gold
+/// count shiny gold              number of bags inside shiny gold
+/// holds-all faded blue, dotted black
+/// can-contain light red, faded blue
+/// path light red, faded blue    shortest containment path
+/// depth [shiny gold]            nesting depth of a bag, or the deepest of all
+/// roots                         bags with no containers
+/// leaves                        bags with no contents
+/// order                         topological order, outermost first
+/// ```
+#[derive(Debug, Eq, PartialEq)]
+pub enum Query {
+    Containers(Bag),
+    Contents(Bag),
+    Count(Bag),
+    HoldsAll(Vec<Bag>),
+    CanContain(Bag, Bag),
+    Path(Bag, Bag),
+    Depth(Option<Bag>),
+    Roots,
+    Leaves,
+    Order,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Answer {
+    Bags(Vec<Bag>),
+    Path(Option<Vec<Bag>>),
+    Bool(bool),
+    Number(BigUint),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum QueryError {
+    Parse(String),
+    Cycle(Cycle),
+}
+
+impl From<Cycle> for QueryError {
+    fn from(cycle: Cycle) -> Self {
+        QueryError::Cycle(cycle)
+    }
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", join(&self.0, " -> "))
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Parse(message) => write!(f, "{}", message),
+            QueryError::Cycle(cycle) => write!(f, "The rules contain a cycle: {}", cycle),
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Bags(bags) => write!(f, "{} bags: {}", bags.len(), join(bags, ", ")),
+            Answer::Path(Some(path)) => write!(f, "{}", join(path, " -> ")),
+            Answer::Path(None) => write!(f, "No path"),
+            Answer::Bool(b) => write!(f, "{}", b),
+            Answer::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+fn join(bags: &[Bag], separator: &str) -> String {
+    bags.iter().map(|b| b.0.as_str()).collect::<Vec<_>>().join(separator)
+}
+
+/// A colour with or without a trailing "bag" or "bags".
+fn parse_bag(s: &str) -> Result<Bag, QueryError> {
+    let name = s.trim();
+    let name = name.strip_suffix(" bags")
+        .or_else(|| name.strip_suffix(" bag"))
+        .unwrap_or(name);
+    if name.is_empty() {
+        return Err(QueryError::Parse("Missing bag colour".to_string()));
+    }
+    Ok(Bag(name.to_string()))
+}
+
+fn parse_bags(s: &str, count: Option<usize>) -> Result<Vec<Bag>, QueryError> {
+    let bags = s.split(',').map(parse_bag).collect::<Result<Vec<_>, _>>()?;
+    match count {
+        Some(n) if bags.len() != n => Err(QueryError::Parse(format!("Expected {} bags, got {}", n, bags.len()))),
+        _ => Ok(bags),
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (command, arguments) = match s.find(' ') {
+            Some(i) => (&s[..i], s[i + 1..].trim()),
+            None => (s, ""),
+        };
+
+        let query = match command {
+            "containers" => Query::Containers(parse_bag(arguments)?),
+            "contents" => Query::Contents(parse_bag(arguments)?),
+            "count" => Query::Count(parse_bag(arguments)?),
+            "holds-all" => Query::HoldsAll(parse_bags(arguments, None)?),
+            "can-contain" | "path" => {
+                let mut bags = parse_bags(arguments, Some(2))?;
+                let inner = bags.pop().unwrap();
+                let outer = bags.pop().unwrap();
+                if command == "path" {
+                    Query::Path(outer, inner)
+                } else {
+                    Query::CanContain(outer, inner)
+                }
+            }
+            "depth" if arguments.is_empty() => Query::Depth(None),
+            "depth" => Query::Depth(Some(parse_bag(arguments)?)),
+            "roots" => Query::Roots,
+            "leaves" => Query::Leaves,
+            "order" => Query::Order,
+            _ => return Err(QueryError::Parse(format!("Unknown query: {}", s))),
+        };
+        Ok(query)
+    }
+}
+
+impl BagGraph {
+    pub fn query(&self, query: &Query) -> Result<Answer, QueryError> {
+        let answer = match query {
+            Query::Containers(bag) => Answer::Bags(cloned(self.containers_of(bag))),
+            Query::Contents(bag) => Answer::Bags(cloned(self.contents_of(bag))),
+            Query::Count(bag) => Answer::Number(self.total_contents(bag)?),
+            Query::HoldsAll(bags) => Answer::Bags(cloned(self.holding_all(bags))),
+            Query::CanContain(outer, inner) => Answer::Bool(self.contents_of(outer).contains(inner)),
+            Query::Path(outer, inner) => Answer::Path(self.shortest_path(outer, inner)),
+            Query::Depth(Some(bag)) => Answer::Number(self.depth(bag)?.into()),
+            Query::Depth(None) => Answer::Number(self.max_depth()?.into()),
+            Query::Roots => Answer::Bags(cloned(self.roots())),
+            Query::Leaves => Answer::Bags(cloned(self.leaves())),
+            Query::Order => Answer::Bags(self.topological_order()?),
+        };
+        Ok(answer)
+    }
+
+    /// Bags that can eventually hold every one of `bags`.
+    pub fn holding_all(&self, bags: &[Bag]) -> BTreeSet<&Bag> {
+        let mut sets = bags.iter().map(|b| self.containers_of(b));
+        let first = sets.next().unwrap_or_default();
+        sets.fold(first, |a, b| a.intersection(&b).copied().collect())
+    }
+
+    /// The shortest chain of bags from `outer` down to `inner`, both included.
+    pub fn shortest_path(&self, outer: &Bag, inner: &Bag) -> Option<Vec<Bag>> {
+        let mut previous: HashMap<&Bag, &Bag> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(outer);
+
+        while let Some(bag) = queue.pop_front() {
+            for (_, b) in self.contents(bag) {
+                if b == outer || previous.contains_key(b) {
+                    continue;
+                }
+                previous.insert(b, bag);
+                if b == inner {
+                    let mut path = vec![b.clone()];
+                    let mut current = b;
+                    while current != outer {
+                        current = previous[current];
+                        path.push(current.clone());
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back(b);
+            }
+        }
+        None
+    }
+
+    /// How many levels of bags there are inside `bag`, an empty bag has depth 0.
+    pub fn depth(&self, bag: &Bag) -> Result<usize, Cycle> {
+        if let Some(cycle) = self.find_cycle_from(bag) {
+            return Err(cycle);
+        }
+        let mut order = Vec::new();
+        self.post_order(bag, &mut HashSet::new(), &mut order);
+        Ok(self.depths(&order)[bag])
+    }
+
+    pub fn max_depth(&self) -> Result<usize, Cycle> {
+        if let Some(cycle) = self.find_cycle() {
+            return Err(cycle);
+        }
+        Ok(self.depths(&self.post_order_all()).values().copied().max().unwrap_or(0))
+    }
+
+    /// The depth of every bag in `order`, which lists the bags inside a bag before it.
+    fn depths<'a>(&'a self, order: &[&'a Bag]) -> HashMap<&'a Bag, usize> {
+        let mut depths = HashMap::new();
+        for bag in order {
+            let depth = self.contents(bag).iter()
+                .map(|(_, b)| 1 + depths[b])
+                .max()
+                .unwrap_or(0);
+            depths.insert(*bag, depth);
+        }
+        depths
+    }
+
+    pub fn roots(&self) -> BTreeSet<&Bag> {
+        self.bags().into_iter().filter(|b| self.direct_containers(b).is_empty()).collect()
+    }
+
+    pub fn leaves(&self) -> BTreeSet<&Bag> {
+        self.bags().into_iter().filter(|b| self.contents(b).is_empty()).collect()
+    }
+
+    /// Every bag comes before all the bags that can be inside it.
+    pub fn topological_order(&self) -> Result<Vec<Bag>, Cycle> {
+        if let Some(cycle) = self.find_cycle() {
+            return Err(cycle);
+        }
+        Ok(self.post_order_all().into_iter().rev().cloned().collect())
+    }
+}
+
+fn cloned(bags: BTreeSet<&Bag>) -> Vec<Bag> {
+    bags.into_iter().cloned().collect()
+}
+
+/// Runs a single query given on the command line, or one query per line from stdin.
+pub fn run(filename: &str, query: &[String]) {
+    let graph = match BagGraph::from_file(filename) {
+        Ok(graph) => graph,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    if !query.is_empty() {
+        println!("{}", answer(&graph, &query.join(" ")));
+        return;
+    }
+
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        };
+        if !line.trim().is_empty() {
+            println!("{}", answer(&graph, &line));
+        }
+    }
+}
+
+fn answer(graph: &BagGraph, query: &str) -> String {
+    match query.parse().and_then(|q| graph.query(&q)) {
+        Ok(answer) => answer.to_string(),
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bag(s: &str) -> Bag {
+        Bag(s.to_string())
+    }
+
+    fn bags(v: &[&str]) -> Vec<Bag> {
+        v.iter().map(|s| bag(s)).collect()
+    }
+
+    fn test_graph() -> BagGraph {
+        BagGraph::from_file("./src/day07/input_test.txt").unwrap()
+    }
+
+    #[test]
+    fn parse_queries() {
+        assert_eq!("containers shiny gold".parse(), Ok(Query::Containers(bag("shiny gold"))));
+        assert_eq!("count shiny gold bags".parse(), Ok(Query::Count(bag("shiny gold"))));
+        assert_eq!("holds-all faded blue, dotted black bag".parse(),
+                   Ok(Query::HoldsAll(bags(&["faded blue", "dotted black"]))));
+        assert_eq!("path light red,faded blue".parse(), Ok(Query::Path(bag("light red"), bag("faded blue"))));
+        assert_eq!("depth".parse(), Ok(Query::Depth(None)));
+        assert_eq!(" roots ".parse(), Ok(Query::Roots));
+        assert!("path light red".parse::<Query>().is_err());
+        assert!("containers".parse::<Query>().is_err());
+        assert!("explode".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn holds_all() {
+        let graph = test_graph();
+        let answer = graph.query(&"holds-all shiny gold, faded blue".parse().unwrap());
+        assert_eq!(answer, Ok(Answer::Bags(bags(&["bright white", "dark orange", "light red", "muted yellow"]))));
+
+        let answer = graph.query(&"holds-all dark olive, vibrant plum".parse().unwrap());
+        assert_eq!(answer, Ok(Answer::Bags(bags(&["bright white", "dark orange", "light red", "muted yellow", "shiny gold"]))));
+    }
+
+    #[test]
+    fn paths() {
+        let graph = test_graph();
+
+        assert_eq!(graph.shortest_path(&bag("light red"), &bag("faded blue")),
+                   Some(bags(&["light red", "muted yellow", "faded blue"])));
+        assert_eq!(graph.shortest_path(&bag("faded blue"), &bag("light red")), None);
+        assert_eq!(graph.shortest_path(&bag("light red"), &bag("light red")), None);
+        assert_eq!(graph.query(&Query::CanContain(bag("dark orange"), bag("dotted black"))), Ok(Answer::Bool(true)));
+        assert_eq!(graph.query(&Query::CanContain(bag("dotted black"), bag("dark orange"))), Ok(Answer::Bool(false)));
+    }
+
+    #[test]
+    fn depth_roots_and_leaves() {
+        let graph = test_graph();
+
+        assert_eq!(graph.depth(&bag("shiny gold")), Ok(2));
+        assert_eq!(graph.depth(&bag("faded blue")), Ok(0));
+        assert_eq!(graph.max_depth(), Ok(4));
+        assert_eq!(cloned(graph.roots()), bags(&["dark orange", "light red"]));
+        assert_eq!(cloned(graph.leaves()), bags(&["dotted black", "faded blue"]));
+    }
+
+    #[test]
+    fn topological_order() {
+        let graph = BagGraph::from_file("./src/day07/input.txt").unwrap();
+        let order = graph.topological_order().unwrap();
+        let position: HashMap<&Bag, usize> = order.iter().enumerate().map(|(i, b)| (b, i)).collect();
+
+        assert_eq!(order.len(), graph.bags().len());
+        for outer in &order {
+            for (_, inner) in graph.contents(outer) {
+                assert!(position[outer] < position[inner]);
+            }
+        }
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let graph = BagGraph::new(vec![
+            "light red bags contain 1 dark red bag.".parse().unwrap(),
+            "dark red bags contain 1 light red bag.".parse().unwrap(),
+        ]);
+
+        assert_eq!(answer(&graph, "order"), "Error: The rules contain a cycle: dark red -> light red -> dark red");
+        assert_eq!(answer(&graph, "path light red, dark red"), "light red -> dark red");
+    }
+
+    #[test]
+    fn answers() {
+        let graph = test_graph();
+
+        assert_eq!(answer(&graph, "count shiny gold"), "32");
+        assert_eq!(answer(&graph, "containers shiny gold"), "4 bags: bright white, dark orange, light red, muted yellow");
+        assert_eq!(answer(&graph, "path shiny gold, faded blue"), "shiny gold -> dark olive -> faded blue");
+        assert_eq!(answer(&graph, "oops"), "Error: Unknown query: oops");
+    }
+}
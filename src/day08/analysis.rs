@@ -0,0 +1,280 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fmt::Write;
+use super::{Instruction, Processor};
+use super::repair::Repair;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Target {
+    Block(usize),
+    /// Exactly one past the last instruction.
+    End,
+    OutOfBounds(i64),
+}
+
+/// Instructions `start..end` that always execute together, followed by `successor`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    pub successor: Target,
+}
+
+/// What can be said about a program without running it. Every instruction has a single
+/// successor, so a loop can never be left once it is entered.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Analysis {
+    pub blocks: Vec<BasicBlock>,
+    pub unreachable: Vec<usize>,
+    /// Each loop in execution order, starting at its lowest address.
+    pub loops: Vec<Vec<usize>>,
+    /// Jumps that leave the program, with their targets.
+    pub exits: Vec<(usize, i64)>,
+    /// Single swaps that make the program terminate.
+    pub repairs: Vec<Repair>,
+}
+
+impl Processor {
+    fn basic_blocks(&self) -> Vec<BasicBlock> {
+        let size = self.program.len();
+        let mut leaders = BTreeSet::new();
+        if size > 0 {
+            leaders.insert(0);
+        }
+        for (address, instruction) in self.program.iter().enumerate() {
+            if let Instruction::Jmp(_) = instruction {
+                let target = instruction.target(address);
+                if target >= 0 && target < size as i64 {
+                    leaders.insert(target as usize);
+                }
+                if address + 1 < size {
+                    leaders.insert(address + 1);
+                }
+            }
+        }
+
+        let starts: Vec<usize> = leaders.into_iter().collect();
+        let block_at = |address: usize| starts.binary_search(&address).unwrap();
+        starts.iter()
+            .enumerate()
+            .map(|(i, start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(size);
+                let last = end - 1;
+                let successor = match self.next_address(last, &self.program[last]) {
+                    Some(next) if next == size => Target::End,
+                    Some(next) => Target::Block(block_at(next)),
+                    None => Target::OutOfBounds(self.program[last].successor(last)),
+                };
+                BasicBlock { start: *start, end, successor }
+            })
+            .collect()
+    }
+
+    fn find_loops(&self) -> Vec<Vec<usize>> {
+        const NEW: u8 = 0;
+        const ON_PATH: u8 = 1;
+        const DONE: u8 = 2;
+        let size = self.program.len();
+        let mut state = vec![NEW; size];
+        let mut loops = Vec::new();
+
+        for start in 0..size {
+            let mut path = Vec::new();
+            let mut address = Some(start);
+            while let Some(current) = address.filter(|a| *a < size && state[*a] == NEW) {
+                state[current] = ON_PATH;
+                path.push(current);
+                address = self.next_address(current, &self.program[current]);
+            }
+            if let Some(repeated) = address.filter(|a| *a < size && state[*a] == ON_PATH) {
+                let mut cycle = path[path.iter().position(|a| *a == repeated).unwrap()..].to_vec();
+                let lowest = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+                cycle.rotate_left(lowest);
+                loops.push(cycle);
+            }
+            for address in path {
+                state[address] = DONE;
+            }
+        }
+
+        loops.sort();
+        loops
+    }
+
+    pub fn analyse(&self) -> Analysis {
+        let size = self.program.len();
+        let mut reachable = vec![false; size];
+        let mut address = Some(0);
+        while let Some(current) = address.filter(|a| *a < size && !reachable[*a]) {
+            reachable[current] = true;
+            address = self.next_address(current, &self.program[current]);
+        }
+
+        let exits = self.program.iter()
+            .enumerate()
+            .filter(|(address, instruction)| self.next_address(*address, instruction).is_none())
+            .map(|(address, instruction)| (address, instruction.successor(address)))
+            .collect();
+
+        Analysis {
+            blocks: self.basic_blocks(),
+            unreachable: (0..size).filter(|a| !reachable[*a]).collect(),
+            loops: self.find_loops(),
+            exits,
+            repairs: self.repairs(),
+        }
+    }
+
+    /// The control-flow graph with a node per basic block. Blocks in loops are filled and
+    /// unreachable blocks are dashed.
+    pub fn cfg_to_dot(&self, analysis: &Analysis) -> String {
+        let in_loop: BTreeSet<usize> = analysis.loops.iter().flatten().copied().collect();
+        let mut out = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+
+        for (i, block) in analysis.blocks.iter().enumerate() {
+            let label: String = (block.start..block.end)
+                .map(|address| format!("{:04} {}\\l", address, self.program[address]))
+                .collect();
+            let style = if in_loop.contains(&block.start) {
+                ", style=filled, fillcolor=salmon"
+            } else if analysis.unreachable.contains(&block.start) {
+                ", style=dashed, color=gray"
+            } else {
+                ""
+            };
+            writeln!(out, "    b{} [label=\"{}\"{}];", i, label, style).unwrap();
+        }
+        writeln!(out, "    end [shape=doublecircle];").unwrap();
+
+        for (i, block) in analysis.blocks.iter().enumerate() {
+            match block.successor {
+                Target::Block(next) => writeln!(out, "    b{} -> b{};", i, next).unwrap(),
+                Target::End => writeln!(out, "    b{} -> end;", i).unwrap(),
+                Target::OutOfBounds(target) => {
+                    writeln!(out, "    out{} [label=\"{}\", shape=octagon, color=red];", i, target).unwrap();
+                    writeln!(out, "    b{} -> out{} [color=red];", i, i).unwrap();
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn addresses(addresses: &[usize]) -> String {
+    if addresses.is_empty() {
+        return "none".to_string();
+    }
+    addresses.iter().map(|a| format!("{:04}", a)).collect::<Vec<_>>().join(", ")
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "blocks:")?;
+        for (i, block) in self.blocks.iter().enumerate() {
+            let successor = match block.successor {
+                Target::Block(next) => format!("b{}", next),
+                Target::End => "end".to_string(),
+                Target::OutOfBounds(target) => format!("out of range ({})", target),
+            };
+            writeln!(f, "  b{:<3} {:04}-{:04} -> {}", i, block.start, block.end - 1, successor)?;
+        }
+        writeln!(f, "unreachable: {}", addresses(&self.unreachable))?;
+        writeln!(f, "loops:")?;
+        for cycle in &self.loops {
+            writeln!(f, "  {} -> {:04}", addresses(cycle).replace(", ", " -> "), cycle[0])?;
+        }
+        let exits: Vec<usize> = self.exits.iter().map(|(address, _)| *address).collect();
+        writeln!(f, "exits: {}", addresses(&exits))?;
+        writeln!(f, "repairs:")?;
+        for repair in &self.repairs {
+            writeln!(f, "  {:04} {} -> {}", repair.address, repair.from, repair.to)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_analysis() -> (Processor, Analysis) {
+        let processor = Processor::from_file("./src/day08/input_test.txt");
+        let analysis = processor.analyse();
+        (processor, analysis)
+    }
+
+    #[test]
+    fn basic_blocks() {
+        let (_, analysis) = test_analysis();
+        let block = |start, end, successor| BasicBlock { start, end, successor };
+
+        assert_eq!(analysis.blocks, vec![
+            block(0, 1, Target::Block(1)),
+            block(1, 3, Target::Block(4)),
+            block(3, 5, Target::Block(1)),
+            block(5, 6, Target::Block(4)),
+            block(6, 8, Target::Block(2)),
+            block(8, 9, Target::End),
+        ]);
+    }
+
+    #[test]
+    fn report() {
+        let (_, analysis) = test_analysis();
+
+        assert_eq!(analysis.unreachable, vec![5, 8]);
+        assert_eq!(analysis.loops, vec![vec![1, 2, 6, 7, 3, 4]]);
+        assert_eq!(analysis.exits, vec![]);
+        assert_eq!(analysis.repairs.iter().map(|r| r.address).collect::<Vec<_>>(), vec![7]);
+        assert_eq!(analysis.to_string(), "\
+blocks:
+  b0   0000-0000 -> b1
+  b1   0001-0002 -> b4
+  b2   0003-0004 -> b1
+  b3   0005-0005 -> b4
+  b4   0006-0007 -> b2
+  b5   0008-0008 -> end
+unreachable: 0005, 0008
+loops:
+  0001 -> 0002 -> 0006 -> 0007 -> 0003 -> 0004 -> 0001
+exits: none
+repairs:
+  0007 jmp -4 -> nop -4
+");
+    }
+
+    #[test]
+    fn exits_and_unreachable_loops() {
+        let processor = Processor::from_source("jmp +3\nloop: acc +1\njmp loop\njmp -9").unwrap();
+        let analysis = processor.analyse();
+
+        assert_eq!(analysis.unreachable, vec![1, 2]);
+        assert_eq!(analysis.loops, vec![vec![1, 2]]);
+        assert_eq!(analysis.exits, vec![(3, -6)]);
+        assert_eq!(analysis.blocks[2].successor, Target::OutOfBounds(-6));
+        assert!(Processor::new(vec![]).analyse().blocks.is_empty());
+    }
+
+    #[test]
+    fn dot() {
+        let (processor, analysis) = test_analysis();
+        let dot = processor.cfg_to_dot(&analysis);
+
+        assert!(dot.starts_with("digraph cfg {\n    node [shape=box, fontname=monospace];\n    b0 [label=\"0000 nop +0\\l\"];\n"));
+        assert!(dot.contains("    b1 [label=\"0001 acc +1\\l0002 jmp +4\\l\", style=filled, fillcolor=salmon];\n"));
+        assert!(dot.contains("    b3 [label=\"0005 acc -99\\l\", style=dashed, color=gray];\n"));
+        assert!(dot.contains("    b4 -> b2;\n    b5 -> end;\n}\n"));
+
+        let processor = Processor::from_source("jmp -1").unwrap();
+        assert!(processor.cfg_to_dot(&processor.analyse()).contains("    b0 -> out0 [color=red];\n"));
+    }
+
+    #[test]
+    fn input_report() {
+        let processor = Processor::from_file("./src/day08/input.txt");
+        let analysis = processor.analyse();
+        assert_eq!(analysis.repairs.len(), 1);
+        println!("{}", analysis);
+    }
+}
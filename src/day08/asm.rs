@@ -0,0 +1,264 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fmt::Write;
+use super::{Instruction, Processor};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum AsmError {
+    UnknownOpcode(String),
+    MissingArgument,
+    InvalidArgument(String),
+    UnexpectedArgument(String),
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UnknownLabel(String),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct AssembleError {
+    /// 1-based line number in the source.
+    pub line: usize,
+    pub error: AsmError,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsmError::UnknownOpcode(opcode) => write!(f, "Unknown opcode {}", opcode),
+            AsmError::MissingArgument => write!(f, "Missing argument"),
+            AsmError::InvalidArgument(argument) => write!(f, "Invalid argument {}", argument),
+            AsmError::UnexpectedArgument(argument) => write!(f, "Unexpected argument {}", argument),
+            AsmError::InvalidLabel(label) => write!(f, "Invalid label {:?}", label),
+            AsmError::DuplicateLabel(label) => write!(f, "Duplicate label {}", label),
+            AsmError::UnknownLabel(label) => write!(f, "Unknown label {}", label),
+        }
+    }
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.error)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Nop(n) => write!(f, "nop {:+}", n),
+            Instruction::Acc(n) => write!(f, "acc {:+}", n),
+            Instruction::Jmp(n) => write!(f, "jmp {:+}", n),
+        }
+    }
+}
+
+pub fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses a single instruction at `address`, jump arguments may name a label instead of an offset.
+pub fn parse_instruction(s: &str, address: usize, labels: &HashMap<String, usize>) -> Result<Instruction, AsmError> {
+    let mut parts = s.split_whitespace();
+    let opcode = parts.next().unwrap_or("");
+    let instruction: fn(i32) -> Instruction = match opcode {
+        "nop" => Instruction::Nop,
+        "acc" => Instruction::Acc,
+        "jmp" => Instruction::Jmp,
+        _ => return Err(AsmError::UnknownOpcode(opcode.to_string())),
+    };
+    let argument = parts.next().ok_or(AsmError::MissingArgument)?;
+    if let Some(extra) = parts.next() {
+        return Err(AsmError::UnexpectedArgument(extra.to_string()));
+    }
+
+    let is_jump = opcode == "jmp" || opcode == "nop";
+    let value = if is_label(argument) && is_jump {
+        match labels.get(argument) {
+            Some(target) => (*target as i64 - address as i64) as i32,
+            None => return Err(AsmError::UnknownLabel(argument.to_string())),
+        }
+    } else {
+        argument.parse().map_err(|_| AsmError::InvalidArgument(argument.to_string()))?
+    };
+
+    Ok(instruction(value))
+}
+
+/// Assembles console source. Everything after `#` or `;` is a comment, blank lines are
+/// ignored and `name:` defines a label for the next instruction, either on its own line
+/// or in front of the instruction.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AssembleError> {
+    let Statements { statements, labels } = statements(source)?;
+    statements.iter()
+        .enumerate()
+        .map(|(address, (line, s))| parse_instruction(s, address, &labels)
+            .map_err(|error| AssembleError { line: *line, error }))
+        .collect()
+}
+
+/// The instructions in the source with their line numbers, and the address of each label.
+pub struct Statements<'a> {
+    pub statements: Vec<(usize, &'a str)>,
+    pub labels: HashMap<String, usize>,
+}
+
+pub fn statements(source: &str) -> Result<Statements<'_>, AssembleError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let mut rest = line.split(['#', ';']).next().unwrap().trim();
+
+        while let Some(colon) = rest.find(':') {
+            let label = rest[..colon].trim();
+            if !is_label(label) {
+                return Err(AssembleError { line: line_number, error: AsmError::InvalidLabel(label.to_string()) });
+            }
+            if labels.insert(label.to_string(), statements.len()).is_some() {
+                return Err(AssembleError { line: line_number, error: AsmError::DuplicateLabel(label.to_string()) });
+            }
+            rest = rest[colon + 1..].trim();
+        }
+
+        if !rest.is_empty() {
+            statements.push((line_number, rest));
+        }
+    }
+
+    Ok(Statements { statements, labels })
+}
+
+fn describe_target(target: i64, program_size: usize) -> String {
+    if target == program_size as i64 {
+        "end".to_string()
+    } else if target < 0 || target > program_size as i64 {
+        format!("out of range ({})", target)
+    } else {
+        format!("{:04}", target)
+    }
+}
+
+/// A listing with the address of every instruction and where each `jmp` goes.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let mut out = String::new();
+    for (address, instruction) in program.iter().enumerate() {
+        match instruction {
+            Instruction::Jmp(_) => {
+                let target = instruction.target(address);
+                writeln!(out, "{:04}  {:<10} ; -> {}", address, instruction.to_string(), describe_target(target, program.len())).unwrap()
+            }
+            _ => writeln!(out, "{:04}  {}", address, instruction).unwrap(),
+        }
+    }
+    out
+}
+
+/// Source that assembles back to the same program, with a label for every `jmp` target
+/// inside the program.
+pub fn to_source(program: &[Instruction]) -> String {
+    let label = |address: i64| format!("l{:04}", address);
+    let targets: BTreeSet<i64> = program.iter()
+        .enumerate()
+        .filter(|(_, i)| matches!(i, Instruction::Jmp(_)))
+        .map(|(address, i)| i.target(address))
+        .filter(|target| *target >= 0 && *target <= program.len() as i64)
+        .collect();
+
+    let mut out = String::new();
+    for (address, instruction) in program.iter().enumerate() {
+        if targets.contains(&(address as i64)) {
+            writeln!(out, "{}:", label(address as i64)).unwrap();
+        }
+        match instruction {
+            Instruction::Jmp(_) if targets.contains(&instruction.target(address)) =>
+                writeln!(out, "    jmp {}", label(instruction.target(address))).unwrap(),
+            _ => writeln!(out, "    {}", instruction).unwrap(),
+        }
+    }
+    if targets.contains(&(program.len() as i64)) {
+        writeln!(out, "{}:", label(program.len() as i64)).unwrap();
+    }
+    out
+}
+
+impl Processor {
+    pub fn from_source(source: &str) -> Result<Self, AssembleError> {
+        Ok(Processor::new(assemble(source)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::read_file;
+    use Instruction::*;
+
+    #[test]
+    fn assemble_with_labels_and_comments() {
+        let source = "\
+# Count down from three
+    acc +3
+loop:               ; decrement
+    acc -1
+    nop +0
+    jmp loop        # back to the start
+done: jmp done
+end:
+";
+        assert_eq!(assemble(source), Ok(vec![Acc(3), Acc(-1), Nop(0), Jmp(-2), Jmp(0)]));
+        assert_eq!(assemble("jmp end\nacc +1\nend:"), Ok(vec![Jmp(2), Acc(1)]));
+        assert_eq!(assemble("start: nop start"), Ok(vec![Nop(0)]));
+    }
+
+    #[test]
+    fn assemble_errors() {
+        let error = |line, error| Err(AssembleError { line, error });
+
+        assert_eq!(assemble("nop +0\nmul +2"), error(2, AsmError::UnknownOpcode("mul".to_string())));
+        assert_eq!(assemble("hlt"), error(1, AsmError::UnknownOpcode("hlt".to_string())));
+        assert_eq!(assemble("hlt x"), error(1, AsmError::UnknownOpcode("hlt".to_string())));
+        assert_eq!(assemble("acc"), error(1, AsmError::MissingArgument));
+        assert_eq!(assemble("acc +1 +2"), error(1, AsmError::UnexpectedArgument("+2".to_string())));
+        assert_eq!(assemble("acc x1"), error(1, AsmError::InvalidArgument("x1".to_string())));
+        assert_eq!(assemble("jmp nowhere"), error(1, AsmError::UnknownLabel("nowhere".to_string())));
+        assert_eq!(assemble("a:\na: nop +0"), error(2, AsmError::DuplicateLabel("a".to_string())));
+        assert_eq!(assemble("two words: nop +0"), error(1, AsmError::InvalidLabel("two words".to_string())));
+
+        assert_eq!(assemble("nop +0\nacc x1").unwrap_err().to_string(), "Line 2: Invalid argument x1");
+        assert_eq!(assemble("hlt").unwrap_err().to_string(), "Line 1: Unknown opcode hlt");
+    }
+
+    #[test]
+    fn disassemble_listing() {
+        let program: Vec<Instruction> = read_file("./src/day08/input_test.txt").unwrap();
+        let listing = disassemble(&program);
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(lines[0], "0000  nop +0");
+        assert_eq!(lines[2], "0002  jmp +4     ; -> 0006");
+        assert_eq!(lines[4], "0004  jmp -3     ; -> 0001");
+        assert_eq!(disassemble(&[Jmp(1)]), "0000  jmp +1     ; -> end\n");
+        assert_eq!(disassemble(&[Jmp(-1)]), "0000  jmp -1     ; -> out of range (-1)\n");
+    }
+
+    #[test]
+    fn source_round_trip() {
+        let program: Vec<Instruction> = read_file("./src/day08/input_test.txt").unwrap();
+        let source = to_source(&program);
+
+        assert!(source.starts_with("    nop +0\nl0001:\n    acc +1\n    jmp l0006\n"));
+        assert_eq!(assemble(&source), Ok(program));
+
+        let program: Vec<Instruction> = read_file("./src/day08/input.txt").unwrap();
+        assert_eq!(assemble(&to_source(&program)), Ok(program));
+    }
+
+    #[test]
+    fn processor_from_source() {
+        let mut processor = Processor::from_source("acc +5\nloop: jmp loop").unwrap();
+        processor.execute();
+        assert_eq!(processor.accumulator, 5);
+    }
+}
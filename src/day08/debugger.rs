@@ -0,0 +1,506 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+use super::{Outcome, Processor};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "==" => Some(Comparison::Equal),
+            "!=" => Some(Comparison::NotEqual),
+            "<" => Some(Comparison::Less),
+            "<=" => Some(Comparison::LessOrEqual),
+            ">" => Some(Comparison::Greater),
+            ">=" => Some(Comparison::GreaterOrEqual),
+            _ => None,
+        }
+    }
+
+    fn holds(&self, a: i32, b: i32) -> bool {
+        match self {
+            Comparison::Equal => a == b,
+            Comparison::NotEqual => a != b,
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Greater => a > b,
+            Comparison::GreaterOrEqual => a >= b,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Breakpoint {
+    /// Stops before the instruction at the address is executed.
+    Address(usize),
+    /// Stops as soon as the accumulator satisfies the condition.
+    Accumulator(Comparison, i32),
+}
+
+impl Breakpoint {
+    /// Either an address, or a condition like `acc >= 10`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        match parts.as_slice() {
+            [address] => address.parse().ok().map(Breakpoint::Address),
+            ["acc", comparison, value] => Some(Breakpoint::Accumulator(Comparison::parse(comparison)?, value.parse().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Address(address) => write!(f, "{:04}", address),
+            Breakpoint::Accumulator(comparison, value) => write!(f, "acc {} {}", comparison.symbol(), value),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Stop {
+    /// A single step finished without anything else happening.
+    Stepped,
+    Breakpoint(Breakpoint),
+    Watch { old: i32, new: i32 },
+    /// The next instruction has already been executed once.
+    Loop(usize),
+    Terminated,
+    /// The instruction couldn't be executed, nothing changed.
+    Fault(Outcome),
+}
+
+pub struct Debugger {
+    processor: Processor,
+    breakpoints: Vec<Breakpoint>,
+    watch_accumulator: bool,
+    visited: Vec<bool>,
+    history: VecDeque<usize>,
+    history_size: usize,
+}
+
+impl Debugger {
+    pub fn new(processor: Processor) -> Self {
+        let mut debugger = Self {
+            processor,
+            breakpoints: Vec::new(),
+            watch_accumulator: false,
+            visited: Vec::new(),
+            history: VecDeque::new(),
+            history_size: 20,
+        };
+        debugger.reset();
+        debugger
+    }
+
+    /// How many executed addresses `backtrace` keeps, dropping the oldest beyond that.
+    pub fn set_history_size(&mut self, history_size: usize) {
+        self.history_size = history_size;
+        while self.history.len() > history_size {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+
+    pub fn reset(&mut self) {
+        self.processor.counter = 0;
+        self.processor.accumulator = 0;
+        if let Some(trace) = self.processor.trace.as_mut() {
+            trace.clear();
+        }
+        self.visited = vec![false; self.processor.program.len()];
+        self.history.clear();
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        if index < self.breakpoints.len() {
+            Some(self.breakpoints.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn watch_accumulator(&mut self, watch: bool) {
+        self.watch_accumulator = watch;
+    }
+
+    /// The most recently executed addresses, oldest first.
+    pub fn backtrace(&self) -> Vec<usize> {
+        self.history.iter().copied().collect()
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.processor.is_terminated()
+    }
+
+    /// Executes one instruction and reports why the debugger would stop afterwards.
+    pub fn step(&mut self) -> Stop {
+        if self.is_terminated() {
+            return Stop::Terminated;
+        }
+
+        let address = self.processor.counter;
+        let old = self.processor.accumulator;
+        if let Err(outcome) = self.processor.step() {
+            return Stop::Fault(outcome);
+        }
+        self.visited[address] = true;
+        self.history.push_back(address);
+        while self.history.len() > self.history_size {
+            self.history.pop_front();
+        }
+        let new = self.processor.accumulator;
+
+        if self.is_terminated() {
+            return Stop::Terminated;
+        }
+        if let Some(breakpoint) = self.breakpoints.iter().find(|b| self.hits(b, old)) {
+            return Stop::Breakpoint(*breakpoint);
+        }
+        if self.watch_accumulator && old != new {
+            return Stop::Watch { old, new };
+        }
+        if self.visited[self.processor.counter] {
+            return Stop::Loop(self.processor.counter);
+        }
+        Stop::Stepped
+    }
+
+    fn hits(&self, breakpoint: &Breakpoint, old_accumulator: i32) -> bool {
+        match breakpoint {
+            Breakpoint::Address(address) => *address == self.processor.counter,
+            // Only when the condition starts to hold, so continuing doesn't stop straight away.
+            Breakpoint::Accumulator(comparison, value) =>
+                comparison.holds(self.processor.accumulator, *value) && !comparison.holds(old_accumulator, *value),
+        }
+    }
+
+    /// Steps until something other than a plain step happens.
+    pub fn run(&mut self) -> Stop {
+        loop {
+            match self.step() {
+                Stop::Stepped => {}
+                stop => return stop,
+            }
+        }
+    }
+
+    /// The instructions around the current one, which is marked with `=>`.
+    pub fn context(&self, radius: usize) -> String {
+        let program = &self.processor.program;
+        let counter = self.processor.counter;
+        let start = counter.saturating_sub(radius).min(program.len());
+        let end = counter.saturating_add(radius + 1).min(program.len());
+
+        let mut out = String::new();
+        for (address, instruction) in program.iter().enumerate().take(end).skip(start) {
+            let marker = if address == counter { "=>" } else { "  " };
+            let seen = if self.visited[address] { "*" } else { " " };
+            out.push_str(&format!("{} {:04}{} {}\n", marker, address, seen, instruction));
+        }
+        if counter >= program.len() {
+            out.push_str(&format!("=> {:04}  <end>\n", counter));
+        }
+        out
+    }
+
+    pub fn state(&self) -> String {
+        format!("counter: {:04}, accumulator: {}", self.processor.counter, self.processor.accumulator)
+    }
+}
+
+fn describe(stop: Stop) -> String {
+    match stop {
+        Stop::Stepped => "Stepped".to_string(),
+        Stop::Breakpoint(breakpoint) => format!("Breakpoint {}", breakpoint),
+        Stop::Watch { old, new } => format!("Accumulator changed from {} to {}", old, new),
+        Stop::Loop(address) => format!("Loop detected, {:04} has already been executed", address),
+        Stop::Terminated => "Program terminated".to_string(),
+        Stop::Fault(outcome) => outcome.to_string(),
+    }
+}
+
+const HELP: &str = "\
+step [n]         execute n instructions (s)
+continue         run until a breakpoint, watch, loop or termination (c)
+break <address>  stop before an address (b)
+break acc <op> <value>
+                 stop when the accumulator starts to satisfy the condition, op is one of
+                 == != < <= > >=
+delete <n>       remove a breakpoint (d)
+breakpoints      list breakpoints
+watch on|off     stop whenever the accumulator changes (w)
+backtrace        recently executed addresses (bt)
+history <n>      keep the last n addresses for backtrace
+list [radius]    instructions around the current one (l)
+print            counter and accumulator (p)
+reset            start over from address 0
+quit             (q)
+";
+
+/// Reads commands from `input` until it ends or `quit` is given.
+pub fn repl<R: BufRead, W: Write>(debugger: &mut Debugger, input: R, mut output: W) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let arguments: Vec<&str> = words.collect();
+        let argument = arguments.join(" ");
+
+        match command {
+            "" => continue,
+            "s" | "step" => {
+                let n = arguments.first().and_then(|n| n.parse().ok()).unwrap_or(1);
+                let mut stop = Stop::Stepped;
+                for _ in 0..n {
+                    stop = debugger.step();
+                    if stop != Stop::Stepped {
+                        break;
+                    }
+                }
+                writeln!(output, "{}", describe(stop))?;
+                write!(output, "{}", debugger.context(2))?;
+            }
+            "c" | "continue" => {
+                writeln!(output, "{}", describe(debugger.run()))?;
+                write!(output, "{}", debugger.context(2))?;
+            }
+            "b" | "break" => match Breakpoint::parse(&argument) {
+                Some(breakpoint) => {
+                    debugger.add_breakpoint(breakpoint);
+                    writeln!(output, "Breakpoint {}", breakpoint)?;
+                }
+                None => writeln!(output, "Invalid breakpoint: {}", argument)?,
+            },
+            "d" | "delete" => match argument.parse().ok().and_then(|i| debugger.remove_breakpoint(i)) {
+                Some(breakpoint) => writeln!(output, "Deleted breakpoint {}", breakpoint)?,
+                None => writeln!(output, "No breakpoint {}", argument)?,
+            },
+            "breakpoints" => {
+                for (i, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                    writeln!(output, "{}: {}", i, breakpoint)?;
+                }
+            }
+            "w" | "watch" => {
+                let watch = argument != "off";
+                debugger.watch_accumulator(watch);
+                writeln!(output, "Watching accumulator: {}", watch)?;
+            }
+            "bt" | "backtrace" => {
+                for address in debugger.backtrace().iter().rev() {
+                    writeln!(output, "{:04} {}", address, debugger.processor().program[*address])?;
+                }
+            }
+            "history" => match argument.parse() {
+                Ok(size) => {
+                    debugger.set_history_size(size);
+                    writeln!(output, "Keeping {} addresses", size)?;
+                }
+                Err(_) => writeln!(output, "Invalid history size: {}", argument)?,
+            },
+            "l" | "list" => {
+                let radius = argument.parse().unwrap_or(5);
+                write!(output, "{}", debugger.context(radius))?;
+            }
+            "p" | "print" => writeln!(output, "{}", debugger.state())?,
+            "reset" => {
+                debugger.reset();
+                writeln!(output, "{}", debugger.state())?;
+            }
+            "q" | "quit" => break,
+            "h" | "help" => write!(output, "{}", HELP)?,
+            _ => writeln!(output, "Unknown command: {}, try help", command)?,
+        }
+    }
+    Ok(())
+}
+
+/// Loads a program from source and debugs it interactively on stdin and stdout.
+pub fn run(filename: &str) {
+    let source = match fs::read_to_string(filename) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("{}: {}", filename, e);
+            process::exit(1);
+        }
+    };
+    match Processor::from_source(&source) {
+        Ok(processor) => {
+            let mut debugger = Debugger::new(processor);
+            let stdin = io::stdin();
+            if let Err(e) = repl(&mut debugger, stdin.lock(), io::stdout()) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_debugger() -> Debugger {
+        Debugger::new(Processor::from_file("./src/day08/input_test.txt"))
+    }
+
+    #[test]
+    fn step_and_loop() {
+        let mut debugger = test_debugger();
+
+        assert_eq!(debugger.step(), Stop::Stepped);
+        assert_eq!(debugger.processor().counter, 1);
+        assert_eq!(debugger.run(), Stop::Loop(1));
+        assert_eq!(debugger.processor().accumulator, 5);
+        assert_eq!(debugger.backtrace(), vec![0, 1, 2, 6, 7, 3, 4]);
+    }
+
+    #[test]
+    fn address_breakpoint() {
+        let mut debugger = test_debugger();
+        debugger.add_breakpoint(Breakpoint::Address(3));
+
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Address(3)));
+        assert_eq!(debugger.processor().counter, 3);
+        assert_eq!(debugger.processor().accumulator, 2);
+        assert_eq!(debugger.run(), Stop::Loop(1));
+    }
+
+    #[test]
+    fn accumulator_breakpoint_and_watch() {
+        let mut debugger = test_debugger();
+        let breakpoint = Breakpoint::parse("acc >= 5").unwrap();
+        debugger.add_breakpoint(breakpoint);
+
+        assert_eq!(debugger.run(), Stop::Breakpoint(breakpoint));
+        assert_eq!(debugger.processor().counter, 4);
+
+        debugger.reset();
+        debugger.remove_breakpoint(0);
+        debugger.watch_accumulator(true);
+        assert_eq!(debugger.run(), Stop::Watch { old: 0, new: 1 });
+        assert_eq!(debugger.run(), Stop::Watch { old: 1, new: 2 });
+    }
+
+    #[test]
+    fn terminate() {
+        let mut debugger = Debugger::new(Processor::from_source("acc +1\njmp +2\nacc +5\nacc +1").unwrap());
+        assert_eq!(debugger.run(), Stop::Terminated);
+        assert_eq!(debugger.processor().accumulator, 2);
+        assert_eq!(debugger.step(), Stop::Terminated);
+
+        let mut debugger = Debugger::new(Processor::from_source("nop +0\njmp -5").unwrap());
+        assert_eq!(debugger.run(), Stop::Fault(Outcome::JumpOutOfBounds { address: 1, target: -4 }));
+        assert_eq!(debugger.state(), "counter: 0001, accumulator: 0");
+        assert_eq!(debugger.backtrace(), vec![0]);
+    }
+
+    #[test]
+    fn parse_breakpoints() {
+        assert_eq!(Breakpoint::parse("12"), Some(Breakpoint::Address(12)));
+        assert_eq!(Breakpoint::parse("acc != -3"), Some(Breakpoint::Accumulator(Comparison::NotEqual, -3)));
+        assert_eq!(Breakpoint::parse("acc ~ 3"), None);
+        assert_eq!(Breakpoint::parse("pc 3"), None);
+    }
+
+    #[test]
+    fn context() {
+        let mut debugger = test_debugger();
+        debugger.step();
+        debugger.step();
+
+        assert_eq!(debugger.context(1), "   0001* acc +1\n=> 0002  jmp +4\n   0003  acc +3\n");
+    }
+
+    #[test]
+    fn history_and_reset() {
+        let mut debugger = Debugger::new(Processor::from_file("./src/day08/input_test.txt").with_trace());
+        debugger.run();
+        assert_eq!(debugger.backtrace().len(), 7);
+        assert_eq!(debugger.processor().trace().map(|t| t.entries().len()), Some(7));
+
+        debugger.set_history_size(2);
+        assert_eq!(debugger.backtrace(), vec![3, 4]);
+
+        debugger.reset();
+        assert!(debugger.backtrace().is_empty());
+        assert_eq!(debugger.processor().trace().map(|t| t.entries().len()), Some(0));
+
+        let mut output = Vec::new();
+        repl(&mut debugger, "history 1\ns 3\nbt\nhistory x\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Keeping 1 addresses\nStepped\n"));
+        assert!(output.ends_with("0002 jmp +4\nInvalid history size: x\n"));
+    }
+
+    #[test]
+    fn repl_session() {
+        let mut debugger = test_debugger();
+        let input = "b 7\nc\nbt\np\nbreakpoints\nd 0\nc\nfoo\nq\np\n";
+        let mut output = Vec::new();
+        repl(&mut debugger, input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "\
+Breakpoint 0007
+Breakpoint 0007
+   0005  acc -99
+   0006* acc +1
+=> 0007  jmp -4
+   0008  acc +6
+0006 acc +1
+0002 jmp +4
+0001 acc +1
+0000 nop +0
+counter: 0007, accumulator: 2
+0: 0007
+Deleted breakpoint 0007
+Loop detected, 0001 has already been executed
+   0000* nop +0
+=> 0001* acc +1
+   0002* jmp +4
+   0003* acc +3
+Unknown command: foo, try help
+");
+    }
+}
@@ -0,0 +1,578 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+use super::Instruction;
+use super::asm::{AsmError, AssembleError, Statements, is_label, statements};
+
+/// The register the base profile's `acc` adds to, it always exists.
+pub const ACC: usize = 0;
+
+/// The range registers and immediates have to stay in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Word {
+    /// The puzzle's accumulator, as used by `Processor`.
+    I32,
+    I64,
+}
+
+impl Word {
+    pub fn fits(self, value: i64) -> bool {
+        match self {
+            Word::I32 => i32::try_from(value).is_ok(),
+            Word::I64 => true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OperandKind {
+    /// A register name.
+    Register,
+    /// A register or an immediate value.
+    Value,
+    /// An immediate value only.
+    Immediate,
+    /// An offset from the instruction's own address, or a label.
+    Offset,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Operand {
+    Register(usize),
+    Immediate(i64),
+}
+
+/// What happens after an operation has executed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Flow {
+    Next,
+    /// Jump relative to the operation's address.
+    Jump(i64),
+    Halt,
+    Overflow,
+}
+
+/// State available to an opcode while its arguments are parsed.
+pub struct ParseContext<'a> {
+    pub address: usize,
+    pub word: Word,
+    labels: &'a HashMap<String, usize>,
+    registers: &'a mut Vec<String>,
+}
+
+impl<'a> ParseContext<'a> {
+    /// The index of a register, registers are created when they are first mentioned.
+    pub fn register(&mut self, name: &str) -> Result<usize, AsmError> {
+        if !is_label(name) {
+            return Err(AsmError::InvalidArgument(name.to_string()));
+        }
+        match self.registers.iter().position(|r| r == name) {
+            Some(index) => Ok(index),
+            None => {
+                self.registers.push(name.to_string());
+                Ok(self.registers.len() - 1)
+            }
+        }
+    }
+
+    pub fn value(&mut self, argument: &str) -> Result<Operand, AsmError> {
+        if is_label(argument) {
+            self.register(argument).map(Operand::Register)
+        } else {
+            self.immediate(argument)
+        }
+    }
+
+    /// A number that fits in the instruction set's word.
+    pub fn immediate(&self, argument: &str) -> Result<Operand, AsmError> {
+        argument.parse()
+            .ok()
+            .filter(|value| self.word.fits(*value))
+            .map(Operand::Immediate)
+            .ok_or_else(|| AsmError::InvalidArgument(argument.to_string()))
+    }
+
+    pub fn offset(&self, argument: &str) -> Result<Operand, AsmError> {
+        if is_label(argument) {
+            match self.labels.get(argument) {
+                Some(target) => Ok(Operand::Immediate(*target as i64 - self.address as i64)),
+                None => Err(AsmError::UnknownLabel(argument.to_string())),
+            }
+        } else {
+            self.immediate(argument)
+        }
+    }
+}
+
+/// An instruction that can be registered with an `InstructionSet`. The default parser
+/// reads one argument for each operand kind.
+pub trait Opcode {
+    fn name(&self) -> &str;
+    fn operands(&self) -> &[OperandKind];
+    fn execute(&self, operands: &[Operand], machine: &mut Machine) -> Flow;
+
+    fn parse(&self, arguments: &[&str], context: &mut ParseContext) -> Result<Vec<Operand>, AsmError> {
+        let kinds = self.operands();
+        if arguments.len() < kinds.len() {
+            return Err(AsmError::MissingArgument);
+        }
+        if let Some(extra) = arguments.get(kinds.len()) {
+            return Err(AsmError::UnexpectedArgument(extra.to_string()));
+        }
+
+        kinds.iter()
+            .zip(arguments)
+            .map(|(kind, argument)| match kind {
+                OperandKind::Register => context.register(argument).map(Operand::Register),
+                OperandKind::Value => context.value(argument),
+                OperandKind::Immediate => context.immediate(argument),
+                OperandKind::Offset => context.offset(argument),
+            })
+            .collect()
+    }
+}
+
+/// An opcode defined by its operands and a function.
+pub struct SimpleOpcode {
+    pub name: &'static str,
+    pub operands: &'static [OperandKind],
+    pub execute: fn(&[Operand], &mut Machine) -> Flow,
+}
+
+impl Opcode for SimpleOpcode {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn operands(&self) -> &[OperandKind] {
+        self.operands
+    }
+
+    fn execute(&self, operands: &[Operand], machine: &mut Machine) -> Flow {
+        (self.execute)(operands, machine)
+    }
+}
+
+fn arithmetic(operands: &[Operand], machine: &mut Machine, f: fn(i64, i64) -> Option<i64>) -> Flow {
+    match f(machine.get(operands[0]), machine.get(operands[1])).filter(|value| machine.word.fits(*value)) {
+        Some(value) => {
+            machine.set(operands[0], value);
+            Flow::Next
+        }
+        None => Flow::Overflow,
+    }
+}
+
+fn jump_if(operands: &[Operand], machine: &Machine, condition: fn(i64) -> bool) -> Flow {
+    if condition(machine.get(operands[0])) {
+        Flow::Jump(machine.get(operands[1]))
+    } else {
+        Flow::Next
+    }
+}
+
+/// A puzzle instruction, executed by `Instruction` itself so the base profile behaves
+/// exactly like `Processor`.
+struct BaseOpcode {
+    name: &'static str,
+    operands: &'static [OperandKind],
+    instruction: fn(i32) -> Instruction,
+}
+
+impl Opcode for BaseOpcode {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn operands(&self) -> &[OperandKind] {
+        self.operands
+    }
+
+    fn execute(&self, operands: &[Operand], machine: &mut Machine) -> Flow {
+        let argument = i32::try_from(machine.get(operands[0]));
+        let accumulator = i32::try_from(machine.registers[ACC]);
+        let (instruction, accumulator) = match (argument, accumulator) {
+            (Ok(argument), Ok(accumulator)) => ((self.instruction)(argument), accumulator),
+            _ => return Flow::Overflow,
+        };
+        match instruction.accumulate(accumulator) {
+            Some(accumulator) => machine.registers[ACC] = accumulator as i64,
+            None => return Flow::Overflow,
+        }
+        Flow::Jump(instruction.successor(machine.counter) - machine.counter as i64)
+    }
+}
+
+fn base_opcodes() -> Vec<BaseOpcode> {
+    use OperandKind::*;
+    vec![
+        BaseOpcode { name: "nop", operands: &[Offset], instruction: Instruction::Nop },
+        BaseOpcode { name: "acc", operands: &[Immediate], instruction: Instruction::Acc },
+        BaseOpcode { name: "jmp", operands: &[Offset], instruction: Instruction::Jmp },
+    ]
+}
+
+/// Replaces the base opcodes, whose arguments have to fit in an `i32`.
+fn extended_opcodes() -> Vec<SimpleOpcode> {
+    use OperandKind::*;
+    vec![
+        SimpleOpcode { name: "nop", operands: &[Offset], execute: |_, _| Flow::Next },
+        SimpleOpcode {
+            name: "acc",
+            operands: &[Value],
+            execute: |operands, machine| arithmetic(&[Operand::Register(ACC), operands[0]], machine, i64::checked_add),
+        },
+        SimpleOpcode { name: "jmp", operands: &[Offset], execute: |operands, machine| Flow::Jump(machine.get(operands[0])) },
+        SimpleOpcode { name: "set", operands: &[Register, Value], execute: |operands, machine| arithmetic(operands, machine, |_, b| Some(b)) },
+        SimpleOpcode { name: "add", operands: &[Register, Value], execute: |operands, machine| arithmetic(operands, machine, i64::checked_add) },
+        SimpleOpcode { name: "mul", operands: &[Register, Value], execute: |operands, machine| arithmetic(operands, machine, i64::checked_mul) },
+        SimpleOpcode { name: "jz", operands: &[Value, Offset], execute: |operands, machine| jump_if(operands, machine, |v| v == 0) },
+        SimpleOpcode { name: "jnz", operands: &[Value, Offset], execute: |operands, machine| jump_if(operands, machine, |v| v != 0) },
+        SimpleOpcode { name: "jgz", operands: &[Value, Offset], execute: |operands, machine| jump_if(operands, machine, |v| v > 0) },
+        SimpleOpcode { name: "jlz", operands: &[Value, Offset], execute: |operands, machine| jump_if(operands, machine, |v| v < 0) },
+        SimpleOpcode {
+            name: "out",
+            operands: &[Value],
+            execute: |operands, machine| {
+                let value = machine.get(operands[0]);
+                machine.output.push(value);
+                Flow::Next
+            },
+        },
+        SimpleOpcode { name: "hlt", operands: &[], execute: |_, _| Flow::Halt },
+    ]
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LoopDetection {
+    /// Executing any address a second time is a loop, which is only right when nothing
+    /// depends on the registers.
+    Address,
+    /// Reaching the same address with the same registers twice is a loop.
+    State,
+}
+
+#[derive(Clone)]
+pub struct InstructionSet {
+    opcodes: HashMap<String, Rc<dyn Opcode>>,
+    loop_detection: LoopDetection,
+    word: Word,
+}
+
+impl InstructionSet {
+    /// `nop`, `acc` and `jmp` with an `i32` accumulator, so programs run exactly as they
+    /// do on `Processor`.
+    pub fn base() -> Self {
+        let mut set = InstructionSet { opcodes: HashMap::new(), loop_detection: LoopDetection::Address, word: Word::I32 };
+        for opcode in base_opcodes() {
+            set.register(Rc::new(opcode));
+        }
+        set
+    }
+
+    /// The base profile plus `i64` registers, which `acc` can add too, arithmetic,
+    /// conditional jumps, `out` and `hlt`.
+    pub fn extended() -> Self {
+        let mut set = InstructionSet::base()
+            .with_loop_detection(LoopDetection::State)
+            .with_word(Word::I64);
+        for opcode in extended_opcodes() {
+            set.register(Rc::new(opcode));
+        }
+        set
+    }
+
+    pub fn with_loop_detection(mut self, loop_detection: LoopDetection) -> Self {
+        self.loop_detection = loop_detection;
+        self
+    }
+
+    pub fn with_word(mut self, word: Word) -> Self {
+        self.word = word;
+        self
+    }
+
+    /// Adds an opcode, returning the one it replaces.
+    pub fn register(&mut self, opcode: Rc<dyn Opcode>) -> Option<Rc<dyn Opcode>> {
+        self.opcodes.insert(opcode.name().to_string(), opcode)
+    }
+
+    /// Assembles source with the same comments and labels as the base assembler.
+    pub fn assemble(&self, source: &str) -> Result<Program, AssembleError> {
+        let Statements { statements, labels } = statements(source)?;
+        let mut registers = vec!["acc".to_string()];
+        let mut operations = Vec::with_capacity(statements.len());
+
+        for (address, (line, statement)) in statements.iter().enumerate() {
+            let mut words = statement.split_whitespace();
+            let name = words.next().unwrap_or("");
+            let arguments: Vec<&str> = words.collect();
+            let error = |error| AssembleError { line: *line, error };
+
+            let opcode = self.opcodes.get(name)
+                .ok_or_else(|| error(AsmError::UnknownOpcode(name.to_string())))?;
+            let mut context = ParseContext { address, word: self.word, labels: &labels, registers: &mut registers };
+            let operands = opcode.parse(&arguments, &mut context).map_err(error)?;
+            operations.push(Operation { opcode: Rc::clone(opcode), operands });
+        }
+
+        Ok(Program { operations, registers, loop_detection: self.loop_detection, word: self.word })
+    }
+}
+
+#[derive(Clone)]
+pub struct Operation {
+    opcode: Rc<dyn Opcode>,
+    operands: Vec<Operand>,
+}
+
+impl Operation {
+    pub fn name(&self) -> &str {
+        self.opcode.name()
+    }
+}
+
+#[derive(Clone)]
+pub struct Program {
+    operations: Vec<Operation>,
+    registers: Vec<String>,
+    loop_detection: LoopDetection,
+    word: Word,
+}
+
+impl Program {
+    pub fn register(&self, name: &str) -> Option<usize> {
+        self.registers.iter().position(|r| r == name)
+    }
+}
+
+/// Source that assembles back to the same program, with offsets instead of labels.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for operation in &self.operations {
+            write!(f, "{}", operation.name())?;
+            for operand in &operation.operands {
+                match operand {
+                    Operand::Register(r) => write!(f, " {}", self.registers[*r])?,
+                    Operand::Immediate(n) => write!(f, " {:+}", n)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Exit {
+    /// The counter reached exactly one past the last operation.
+    Terminated,
+    Halted(usize),
+    Loop(usize),
+    JumpOutOfBounds { address: usize, target: i64 },
+    Overflow(usize),
+    StepLimit,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Machine {
+    pub counter: usize,
+    pub registers: Vec<i64>,
+    pub output: Vec<i64>,
+    /// Results outside the word overflow.
+    pub word: Word,
+}
+
+impl Machine {
+    pub fn new(program: &Program) -> Self {
+        Machine { counter: 0, registers: vec![0; program.registers.len()], output: Vec::new(), word: program.word }
+    }
+
+    pub fn get(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(r) => self.registers[r],
+            Operand::Immediate(n) => n,
+        }
+    }
+
+    /// Writing to an immediate operand does nothing.
+    pub fn set(&mut self, operand: Operand, value: i64) {
+        if let Operand::Register(r) = operand {
+            self.registers[r] = value;
+        }
+    }
+
+    /// Runs from the current state until the program exits or `step_limit` operations
+    /// have executed.
+    pub fn run(&mut self, program: &Program, step_limit: usize) -> Exit {
+        let size = program.operations.len();
+        let mut visited = vec![false; size];
+        let mut seen = HashSet::new();
+
+        for _ in 0..step_limit {
+            if self.counter == size {
+                return Exit::Terminated;
+            }
+            let repeated = match program.loop_detection {
+                LoopDetection::Address => std::mem::replace(&mut visited[self.counter], true),
+                LoopDetection::State => !seen.insert((self.counter, self.registers.clone())),
+            };
+            if repeated {
+                return Exit::Loop(self.counter);
+            }
+
+            let address = self.counter;
+            let operation = &program.operations[address];
+            let target = match operation.opcode.execute(&operation.operands, self) {
+                Flow::Next => address as i64 + 1,
+                Flow::Jump(offset) => match (address as i64).checked_add(offset) {
+                    Some(target) => target,
+                    None => return Exit::Overflow(address),
+                },
+                Flow::Halt => return Exit::Halted(address),
+                Flow::Overflow => return Exit::Overflow(address),
+            };
+            if target < 0 || target > size as i64 {
+                return Exit::JumpOutOfBounds { address, target };
+            }
+            self.counter = target as usize;
+        }
+
+        if self.counter == size {
+            Exit::Terminated
+        } else {
+            Exit::StepLimit
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::day08::{Outcome, Processor};
+
+    const LIMIT: usize = 100_000;
+
+    fn run(set: &InstructionSet, source: &str) -> (Exit, Machine) {
+        let program = set.assemble(source).unwrap();
+        let mut machine = Machine::new(&program);
+        let exit = machine.run(&program, LIMIT);
+        (exit, machine)
+    }
+
+    #[test]
+    fn base_profile() {
+        let source = fs::read_to_string("./src/day08/input.txt").unwrap();
+        let (exit, machine) = run(&InstructionSet::base(), &source);
+        assert!(matches!(exit, Exit::Loop(_)));
+        assert_eq!(machine.registers[ACC], 1475);
+
+        let (repaired, _) = Processor::from_file("./src/day08/input.txt").change_to_terminate();
+        let source: String = repaired.program.iter().map(|i| format!("{}\n", i)).collect();
+        let (exit, machine) = run(&InstructionSet::base(), &source);
+        assert_eq!(exit, Exit::Terminated);
+        assert_eq!(machine.registers[ACC], repaired.accumulator as i64);
+
+        assert_eq!(InstructionSet::base().assemble("mul a +2").err(),
+                   Some(AssembleError { line: 1, error: AsmError::UnknownOpcode("mul".to_string()) }));
+    }
+
+    #[test]
+    fn base_matches_processor() {
+        let exit = |outcome| match outcome {
+            Outcome::Terminated => Exit::Terminated,
+            Outcome::JumpOutOfBounds { address, target } => Exit::JumpOutOfBounds { address, target },
+            Outcome::InfiniteLoop(address) => Exit::Loop(address),
+            Outcome::Overflow(address) => Exit::Overflow(address),
+        };
+        let input = fs::read_to_string("./src/day08/input.txt").unwrap();
+        let sources = [
+            "acc +2147483647\nacc +1",
+            "acc -2147483648\nacc -1",
+            "acc +2147483647\nacc -1\nacc +1",
+            "nop +0\nacc +1\njmp -2",
+            "loop: acc +3\njmp loop",
+            "jmp +2\nacc +1",
+            "jmp +3\nacc +1",
+            "acc +1\njmp -2",
+            "",
+            &input,
+        ];
+
+        for source in sources.iter() {
+            let mut processor = Processor::from_source(source).unwrap();
+            let expected = exit(processor.execute());
+            let (actual, machine) = run(&InstructionSet::base(), source);
+            assert_eq!(actual, expected, "{:?}", source);
+            assert_eq!(machine.registers[ACC], processor.accumulator as i64, "{:?}", source);
+        }
+
+        for source in ["acc x1", "acc +2147483648", "jmp -2147483649", "jmp nowhere", "acc", "nop +1 +2", "hlt"].iter() {
+            assert_eq!(InstructionSet::base().assemble(source).err(), Processor::from_source(source).err(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn extended_profile() {
+        let source = "\
+    set n +5
+    set a +1
+loop:
+    mul a n
+    add n -1
+    jnz n loop
+    out a
+    hlt
+    out +0
+";
+        let program = InstructionSet::extended().assemble(source).unwrap();
+        let mut machine = Machine::new(&program);
+
+        assert_eq!(machine.run(&program, LIMIT), Exit::Halted(6));
+        assert_eq!(machine.output, vec![120]);
+        assert_eq!(machine.registers[program.register("n").unwrap()], 0);
+        assert_eq!(program.to_string().lines().nth(4), Some("jnz n -2"));
+        assert_eq!(InstructionSet::extended().assemble(&program.to_string()).unwrap().to_string(), program.to_string());
+    }
+
+    #[test]
+    fn extended_exits() {
+        let set = InstructionSet::extended();
+
+        assert_eq!(run(&set, "set a +1\nloop: jnz a loop").0, Exit::Loop(1));
+        assert_eq!(run(&set, "set a +3\nadd a -1\njgz a -1\nacc a").0, Exit::Terminated);
+        assert_eq!(run(&set, "set a +9223372036854775807\nadd a +1").0, Exit::Overflow(1));
+        assert_eq!(run(&set, "acc +2147483647\nacc +1").0, Exit::Terminated);
+        assert_eq!(run(&set, "jz acc -2").0, Exit::JumpOutOfBounds { address: 0, target: -2 });
+        assert_eq!(run(&set, "nop +0\njmp +9223372036854775807").0, Exit::Overflow(1));
+        assert_eq!(run(&set, "jmp -9223372036854775808").0, Exit::JumpOutOfBounds { address: 0, target: i64::MIN });
+        assert_eq!(run(&set, "loop: add a +1\njmp loop").0, Exit::StepLimit);
+        assert_eq!(set.assemble("add +1 +1").err().map(|e| e.error), Some(AsmError::InvalidArgument("+1".to_string())));
+        assert_eq!(set.assemble("hlt now").err().map(|e| e.error), Some(AsmError::UnexpectedArgument("now".to_string())));
+    }
+
+    #[test]
+    fn register_opcode() {
+        let mut set = InstructionSet::base();
+        let double = SimpleOpcode {
+            name: "dbl",
+            operands: &[OperandKind::Register],
+            execute: |operands, machine| {
+                let value = machine.get(operands[0]) * 2;
+                machine.set(operands[0], value);
+                Flow::Next
+            },
+        };
+
+        assert!(set.register(Rc::new(double)).is_none());
+        let (exit, machine) = run(&set, "acc +3\ndbl acc\ndbl acc");
+        assert_eq!(exit, Exit::Terminated);
+        assert_eq!(machine.registers[ACC], 12);
+
+        let halt = SimpleOpcode { name: "nop", operands: &[OperandKind::Offset], execute: |_, _| Flow::Halt };
+        assert!(set.register(Rc::new(halt)).is_some());
+        assert_eq!(run(&set, "acc +1\nnop +0").0, Exit::Halted(1));
+    }
+}
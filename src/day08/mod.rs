@@ -1,5 +1,18 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 use crate::util::read_file;
+use crate::day08::asm::{AsmError, parse_instruction};
+use crate::day08::trace::{State, Trace, TraceEntry};
+
+mod analysis;
+mod asm;
+mod debugger;
+mod isa;
+mod repair;
+mod trace;
+
+pub use debugger::run as run_debugger;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum Instruction {
@@ -8,17 +21,60 @@ enum Instruction {
     Jmp(i32),
 }
 
+impl Instruction {
+    /// Where a jump at `address` would go, which may be outside the program.
+    fn target(&self, address: usize) -> i64 {
+        match self {
+            Instruction::Nop(delta) | Instruction::Acc(delta) | Instruction::Jmp(delta) =>
+                address as i64 + *delta as i64,
+        }
+    }
+
+    /// The address executed after this instruction at `address`.
+    fn successor(&self, address: usize) -> i64 {
+        match self {
+            Instruction::Jmp(_) => self.target(address),
+            _ => address as i64 + 1,
+        }
+    }
+
+    /// The accumulator after this instruction, `None` if it would overflow.
+    fn accumulate(&self, accumulator: i32) -> Option<i32> {
+        match self {
+            Instruction::Acc(delta) => accumulator.checked_add(*delta),
+            _ => Some(accumulator),
+        }
+    }
+}
+
 impl FromStr for Instruction {
-    type Err = ();
+    type Err = AsmError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<_> = s.split(" ").collect();
-        let argument = parts[1].parse().unwrap();
-        match parts[0] {
-            "nop" => Ok(Instruction::Nop(argument)),
-            "acc" => Ok(Instruction::Acc(argument)),
-            "jmp" => Ok(Instruction::Jmp(argument)),
-            _ => Err(()),
+        parse_instruction(s, 0, &HashMap::new())
+    }
+}
+
+/// How a run of the processor ended.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Outcome {
+    /// The counter reached exactly one past the last instruction.
+    Terminated,
+    JumpOutOfBounds { address: usize, target: i64 },
+    /// The instruction at the address was about to be executed a second time.
+    InfiniteLoop(usize),
+    /// The accumulator would overflow at the address.
+    Overflow(usize),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Terminated => write!(f, "Program terminated"),
+            Outcome::JumpOutOfBounds { address, target } =>
+                write!(f, "Jump out of bounds from {:04} to {}", address, target),
+            Outcome::InfiniteLoop(address) => write!(f, "Loop detected, {:04} has already been executed", address),
+            Outcome::Overflow(address) => write!(f, "Accumulator overflow at {:04}", address),
         }
     }
 }
@@ -28,44 +84,91 @@ struct Processor {
     program: Vec<Instruction>,
     counter: usize,
     accumulator: i32,
+    trace: Option<Trace>,
 }
 
 impl Processor {
-    fn from_file(filename: &str) -> Self {
-        let program = read_file(filename).unwrap();
+    fn new(program: Vec<Instruction>) -> Self {
         Processor {
             program,
             counter: 0,
             accumulator: 0,
+            trace: None,
+        }
+    }
+
+    /// Records every executed instruction, the trace is cleared each time `execute` starts over.
+    fn with_trace(mut self) -> Self {
+        self.trace = Some(Trace::default());
+        self
+    }
+
+    fn state(&self) -> State {
+        State { counter: self.counter, accumulator: self.accumulator }
+    }
+
+    fn from_file(filename: &str) -> Self {
+        Processor::new(read_file(filename).unwrap())
+    }
+
+    /// The address after `address` if it is in the program or exactly one past the end.
+    fn next_address(&self, address: usize, instruction: &Instruction) -> Option<usize> {
+        let next = instruction.successor(address);
+        if next >= 0 && next <= self.program.len() as i64 {
+            Some(next as usize)
+        } else {
+            None
         }
     }
 
     fn is_terminated(&self) -> bool {
-        self.counter >= self.program.len()
+        self.counter == self.program.len()
     }
 
-    fn execute(&mut self) {
+    fn execute(&mut self) -> Outcome {
         self.counter = 0;
         self.accumulator = 0;
         let program_size = self.program.len();
         let mut visited = vec![false; program_size];
+        if let Some(trace) = self.trace.as_mut() {
+            trace.clear();
+        }
 
-        while self.counter < program_size && !visited[self.counter] {
+        loop {
+            if self.counter == program_size {
+                return Outcome::Terminated;
+            }
+            if visited[self.counter] {
+                return Outcome::InfiniteLoop(self.counter);
+            }
             visited[self.counter] = true;
-            self.handle_operation();
+            if let Err(outcome) = self.step() {
+                return outcome;
+            }
         }
     }
 
-    fn handle_operation(&mut self) {
-        match self.program[self.counter] {
-            Instruction::Acc(delta) => {
-                self.accumulator += delta;
-                self.counter += 1;
-            },
-            // Make counter signed while adding the signed delta value
-            Instruction::Jmp(delta) => self.counter = ((self.counter as i32) + delta) as usize,
-            _ => self.counter += 1,
+    /// Executes the current instruction. On an error the state is left as it was.
+    fn step(&mut self) -> Result<(), Outcome> {
+        let before = self.state();
+        let instruction = self.program[self.counter];
+        self.handle_operation()?;
+        let after = self.state();
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceEntry { instruction, before, after });
         }
+        Ok(())
+    }
+
+    fn handle_operation(&mut self) -> Result<(), Outcome> {
+        let address = self.counter;
+        let instruction = self.program[address];
+        let accumulator = instruction.accumulate(self.accumulator).ok_or(Outcome::Overflow(address))?;
+        let next = self.next_address(address, &instruction)
+            .ok_or(Outcome::JumpOutOfBounds { address, target: instruction.successor(address) })?;
+        self.accumulator = accumulator;
+        self.counter = next;
+        Ok(())
     }
 
     fn swap_at(&mut self, index: usize) -> bool {
@@ -82,16 +185,16 @@ impl Processor {
     }
 
     fn change_to_terminate(self) -> (Self, usize) {
-        for i in 0..self.program.len() {
-            let mut cloned_processor = self.clone();
-            if cloned_processor.swap_at(i) {
-                cloned_processor.execute();
-                if cloned_processor.is_terminated() {
-                    return (cloned_processor, i);
-                }
+        match self.repair() {
+            Some(repair) => {
+                let mut repaired = self.clone();
+                repaired.swap_at(repair.address);
+                let outcome = repaired.execute();
+                debug_assert_eq!(outcome, Outcome::Terminated);
+                (repaired, repair.address)
             }
+            None => (self, 0),
         }
-        return (self, 0);
     }
 }
 
@@ -104,6 +207,8 @@ mod tests {
         assert_eq!("nop +0".parse::<Instruction>().unwrap(), Instruction::Nop(0));
         assert_eq!("acc +12".parse::<Instruction>().unwrap(), Instruction::Acc(12));
         assert_eq!("jmp -3".parse::<Instruction>().unwrap(), Instruction::Jmp(-3));
+        assert_eq!("jmp".parse::<Instruction>(), Err(AsmError::MissingArgument));
+        assert_eq!("acc three".parse::<Instruction>(), Err(AsmError::InvalidArgument("three".to_string())));
     }
 
     #[test]
@@ -116,10 +221,25 @@ mod tests {
     #[test]
     fn part_1() {
         let mut processor = Processor::from_file("./src/day08/input.txt");
-        processor.execute();
+        assert!(matches!(processor.execute(), Outcome::InfiniteLoop(_)));
         assert_eq!(processor.accumulator, 1475);
     }
 
+    #[test]
+    fn outcomes() {
+        let run = |program: Vec<Instruction>| Processor::new(program).execute();
+
+        assert_eq!(run(vec![Instruction::Nop(0), Instruction::Jmp(1)]), Outcome::Terminated);
+        assert_eq!(run(vec![Instruction::Acc(1), Instruction::Jmp(-1)]), Outcome::InfiniteLoop(0));
+        assert_eq!(run(vec![Instruction::Nop(0), Instruction::Jmp(-2)]), Outcome::JumpOutOfBounds { address: 1, target: -1 });
+        assert_eq!(run(vec![Instruction::Jmp(2)]), Outcome::JumpOutOfBounds { address: 0, target: 2 });
+        assert_eq!(run(vec![]), Outcome::Terminated);
+
+        let mut processor = Processor::new(vec![Instruction::Acc(i32::MAX), Instruction::Acc(1)]);
+        assert_eq!(processor.execute(), Outcome::Overflow(1));
+        assert_eq!((processor.counter, processor.accumulator), (1, i32::MAX));
+    }
+
     #[test]
     fn change_to_terminate() {
         let processor = Processor::from_file("./src/day08/input_test.txt");
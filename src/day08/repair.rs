@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+use super::{Instruction, Processor};
+
+/// Swapping the `nop` or `jmp` at `address`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Repair {
+    pub address: usize,
+    pub from: Instruction,
+    pub to: Instruction,
+}
+
+impl Instruction {
+    fn swapped(&self) -> Option<Instruction> {
+        match self {
+            Instruction::Nop(delta) => Some(Instruction::Jmp(*delta)),
+            Instruction::Jmp(delta) => Some(Instruction::Nop(*delta)),
+            Instruction::Acc(_) => None,
+        }
+    }
+}
+
+impl Processor {
+    /// For every address, whether running from there ends exactly one past the last
+    /// instruction. Jumps anywhere else never terminate.
+    pub fn terminating_addresses(&self) -> Vec<bool> {
+        let size = self.program.len();
+        let mut predecessors = vec![Vec::new(); size + 1];
+        for (address, instruction) in self.program.iter().enumerate() {
+            if let Some(next) = self.next_address(address, instruction) {
+                predecessors[next].push(address);
+            }
+        }
+
+        let mut terminates = vec![false; size + 1];
+        terminates[size] = true;
+        let mut queue = vec![size];
+        while let Some(address) = queue.pop() {
+            for previous in &predecessors[address] {
+                if !terminates[*previous] {
+                    terminates[*previous] = true;
+                    queue.push(*previous);
+                }
+            }
+        }
+        terminates.truncate(size);
+        terminates
+    }
+
+    /// The addresses executed from the start, in order, until one repeats or the
+    /// program is left.
+    fn execution_path(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.program.len()];
+        let mut path = Vec::new();
+        let mut address = Some(0);
+        while let Some(current) = address.filter(|a| *a < self.program.len() && !visited[*a]) {
+            visited[current] = true;
+            path.push(current);
+            address = self.next_address(current, &self.program[current]);
+        }
+        path
+    }
+
+    /// Every single swap that makes a program that doesn't terminate do so, by address.
+    /// Only instructions that are executed matter, and swapping one of them can't lead
+    /// back into the loop because none of them reach the end.
+    pub fn repairs(&self) -> Vec<Repair> {
+        let terminates = self.terminating_addresses();
+        if terminates.first().copied().unwrap_or(true) {
+            return Vec::new();
+        }
+
+        let mut repairs: Vec<Repair> = self.execution_path().into_iter()
+            .filter_map(|address| {
+                let from = self.program[address];
+                let to = from.swapped()?;
+                let next = self.next_address(address, &to)?;
+                if next == self.program.len() || terminates[next] {
+                    Some(Repair { address, from, to })
+                } else {
+                    None
+                }
+            })
+            .collect();
+        repairs.sort_by_key(|r| r.address);
+        repairs
+    }
+
+    pub fn repair(&self) -> Option<Repair> {
+        self.repairs().into_iter().next()
+    }
+
+    /// The fewest swaps that make the program terminate, empty if it already does. There is
+    /// always an answer, turning every `jmp` into a `nop` works.
+    /// A shortest path from the start to the end where following an instruction is free
+    /// and swapping it costs one never visits an address twice, so it can't loop.
+    pub fn minimal_repair(&self) -> Vec<Repair> {
+        let size = self.program.len();
+        let mut cost = vec![usize::MAX; size + 1];
+        let mut via: Vec<Option<(usize, bool)>> = vec![None; size + 1];
+        let mut queue = VecDeque::new();
+        cost[0] = 0;
+        queue.push_back(0);
+
+        while let Some(address) = queue.pop_front() {
+            if address == size {
+                break;
+            }
+            let instruction = self.program[address];
+            let options = [(Some(instruction), false), (instruction.swapped(), true)];
+            for (next_instruction, swapped) in options.iter() {
+                let next = next_instruction.and_then(|i| self.next_address(address, &i));
+                if let Some(next) = next {
+                    let next_cost = cost[address] + *swapped as usize;
+                    if next_cost < cost[next] {
+                        cost[next] = next_cost;
+                        via[next] = Some((address, *swapped));
+                        if *swapped {
+                            queue.push_back(next);
+                        } else {
+                            queue.push_front(next);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut repairs = Vec::new();
+        let mut address = size;
+        while let Some((previous, swapped)) = via[address] {
+            if swapped {
+                let from = self.program[previous];
+                repairs.push(Repair { address: previous, from, to: from.swapped().unwrap() });
+            }
+            address = previous;
+        }
+        repairs.sort_by_key(|r| r.address);
+        repairs
+    }
+
+    /// A copy of the program with the repairs applied.
+    pub fn repaired(&self, repairs: &[Repair]) -> Processor {
+        let mut processor = self.clone();
+        for repair in repairs {
+            processor.program[repair.address] = repair.to;
+        }
+        processor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day08::Outcome;
+    use crate::day08::asm::assemble;
+
+    fn brute_force(processor: &Processor) -> Vec<usize> {
+        (0..processor.program.len())
+            .filter(|i| {
+                let mut swapped = processor.clone();
+                swapped.swap_at(*i) && swapped.execute() == Outcome::Terminated
+            })
+            .collect()
+    }
+
+    #[test]
+    fn terminating_addresses() {
+        let processor = Processor::from_file("./src/day08/input_test.txt");
+        let terminates = processor.terminating_addresses();
+
+        assert_eq!(terminates, vec![false, false, false, false, false, false, false, false, true]);
+        assert_eq!(Processor::new(assemble("jmp +2\njmp -9\nacc +1").unwrap()).terminating_addresses(),
+                   vec![true, false, true]);
+    }
+
+    #[test]
+    fn single_repair() {
+        let processor = Processor::from_file("./src/day08/input_test.txt");
+
+        assert_eq!(processor.repair(), Some(Repair {
+            address: 7,
+            from: Instruction::Jmp(-4),
+            to: Instruction::Nop(-4),
+        }));
+        let mut repaired = processor.repaired(&[processor.repair().unwrap()]);
+        repaired.execute();
+        assert_eq!(repaired.accumulator, 8);
+        assert_eq!(repaired.repairs(), vec![]);
+    }
+
+    #[test]
+    fn all_repairs_match_brute_force() {
+        let processor = Processor::new(assemble("nop +3\njmp -1\nacc +0").unwrap());
+        let addresses: Vec<usize> = processor.repairs().iter().map(|r| r.address).collect();
+
+        assert_eq!(addresses, vec![0, 1]);
+        assert_eq!(addresses, brute_force(&processor));
+
+        let processor = Processor::from_file("./src/day08/input.txt");
+        let addresses: Vec<usize> = processor.repairs().iter().map(|r| r.address).collect();
+        assert_eq!(addresses, brute_force(&processor));
+    }
+
+    #[test]
+    fn minimal_repair() {
+        let processor = Processor::new(assemble("jmp +0\njmp +0\nacc +1").unwrap());
+        assert_eq!(processor.repairs(), vec![]);
+
+        let repairs = processor.minimal_repair();
+        assert_eq!(repairs.iter().map(|r| r.address).collect::<Vec<_>>(), vec![0, 1]);
+        let mut repaired = processor.repaired(&repairs);
+        repaired.execute();
+        assert_eq!((repaired.counter, repaired.accumulator), (3, 1));
+
+        let processor = Processor::from_file("./src/day08/input_test.txt");
+        assert_eq!(processor.minimal_repair().len(), 1);
+        assert_eq!(Processor::new(vec![Instruction::Acc(1)]).minimal_repair(), vec![]);
+    }
+}
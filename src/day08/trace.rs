@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use super::{Instruction, Outcome, Processor};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct State {
+    pub counter: usize,
+    pub accumulator: i32,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TraceEntry {
+    pub instruction: Instruction,
+    pub before: State,
+    pub after: State,
+}
+
+/// The addresses executed once per turn of the loop `execute` stopped on, starting and
+/// ending just before the repeated address is executed again.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LoopReport {
+    pub cycle: Vec<usize>,
+    /// Executed instructions before the loop was entered.
+    pub lead_in: usize,
+    /// How much each turn of the loop changes the accumulator.
+    pub accumulator_delta: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    entries: Vec<TraceEntry>,
+}
+
+impl Trace {
+    pub fn push(&mut self, entry: TraceEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// One line per executed instruction: step, address, instruction and the state change.
+    pub fn write_to<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for (step, entry) in self.entries.iter().enumerate() {
+            writeln!(out, "{:>6} {:04} {:<10} acc {} -> {}, next {:04}",
+                     step,
+                     entry.before.counter,
+                     entry.instruction.to_string(),
+                     entry.before.accumulator,
+                     entry.after.accumulator,
+                     entry.after.counter)?;
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, filename: P) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(filename)?);
+        self.write_to(&mut out)?;
+        out.flush()
+    }
+
+    /// The loop the trace ended in, if the last instruction goes back to an address that
+    /// has already been executed.
+    pub fn loop_report(&self) -> Option<LoopReport> {
+        let last = self.entries.last()?;
+        let repeated = last.after.counter;
+        let start = self.entries.iter().position(|e| e.before.counter == repeated)?;
+
+        Some(LoopReport {
+            cycle: self.entries[start..].iter().map(|e| e.before.counter).collect(),
+            lead_in: start,
+            accumulator_delta: last.after.accumulator as i64 - self.entries[start].before.accumulator as i64,
+        })
+    }
+
+    pub fn replay(&self) -> Replay<'_> {
+        Replay { trace: self, position: self.entries.len() }
+    }
+}
+
+/// Moves backwards and forwards through a recorded trace. The position is the number of
+/// instructions executed so far, starting at the end of the trace.
+pub struct Replay<'a> {
+    trace: &'a Trace,
+    position: usize,
+}
+
+impl<'a> Replay<'a> {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn state(&self) -> Option<State> {
+        match self.position {
+            0 => self.trace.entries.first().map(|e| e.before),
+            p => Some(self.trace.entries[p - 1].after),
+        }
+    }
+
+    /// Undoes the most recent instruction, returning it.
+    pub fn step_back(&mut self) -> Option<&'a TraceEntry> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        Some(&self.trace.entries[self.position])
+    }
+
+    /// Redoes the next instruction, returning it.
+    pub fn step_forward(&mut self) -> Option<&'a TraceEntry> {
+        let entry = self.trace.entries.get(self.position)?;
+        self.position += 1;
+        Some(entry)
+    }
+
+    /// Steps back until just before `address` was last executed.
+    pub fn back_to(&mut self, address: usize) -> Option<State> {
+        while let Some(entry) = self.step_back() {
+            if entry.before.counter == address {
+                return Some(entry.before);
+            }
+        }
+        None
+    }
+}
+
+impl Processor {
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Runs a traced copy of the processor and reports the loop it stops on, if any.
+    pub fn find_loop(&self) -> Option<LoopReport> {
+        let mut traced = self.clone().with_trace();
+        match traced.execute() {
+            Outcome::InfiniteLoop(_) => traced.trace()?.loop_report(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traced_test_program() -> Processor {
+        let mut processor = Processor::from_file("./src/day08/input_test.txt").with_trace();
+        processor.execute();
+        processor
+    }
+
+    #[test]
+    fn record_trace() {
+        let processor = traced_test_program();
+        let entries = processor.trace().unwrap().entries();
+
+        assert_eq!(entries.len(), 7);
+        assert_eq!(entries[1], TraceEntry {
+            instruction: Instruction::Acc(1),
+            before: State { counter: 1, accumulator: 0 },
+            after: State { counter: 2, accumulator: 1 },
+        });
+        assert_eq!(entries[6].after, State { counter: 1, accumulator: 5 });
+        assert!(Processor::from_file("./src/day08/input_test.txt").trace().is_none());
+    }
+
+    #[test]
+    fn trace_is_cleared_on_execute() {
+        let mut processor = traced_test_program();
+        processor.execute();
+        assert_eq!(processor.trace().unwrap().entries().len(), 7);
+    }
+
+    #[test]
+    fn write_trace() {
+        let processor = traced_test_program();
+        let mut out = Vec::new();
+        processor.trace().unwrap().write_to(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(text.lines().next(), Some("     0 0000 nop +0     acc 0 -> 0, next 0001"));
+        assert_eq!(text.lines().last(), Some("     6 0004 jmp -3     acc 5 -> 5, next 0001"));
+
+        let filename = std::env::temp_dir().join("day08_trace.txt");
+        processor.trace().unwrap().save(&filename).unwrap();
+        assert_eq!(std::fs::read_to_string(&filename).unwrap(), text);
+        std::fs::remove_file(&filename).unwrap();
+    }
+
+    #[test]
+    fn reverse_stepping() {
+        let processor = traced_test_program();
+        let trace = processor.trace().unwrap();
+        let mut replay = trace.replay();
+
+        assert_eq!(replay.state(), Some(State { counter: 1, accumulator: 5 }));
+        assert_eq!(replay.step_back().map(|e| e.instruction), Some(Instruction::Jmp(-3)));
+        assert_eq!(replay.state(), Some(State { counter: 4, accumulator: 5 }));
+        assert_eq!(replay.back_to(6), Some(State { counter: 6, accumulator: 1 }));
+        assert_eq!(replay.position(), 3);
+        assert_eq!(replay.step_forward().map(|e| e.after), Some(State { counter: 7, accumulator: 2 }));
+
+        while replay.step_back().is_some() {}
+        assert_eq!(replay.position(), 0);
+        assert_eq!(replay.state(), Some(State { counter: 0, accumulator: 0 }));
+        assert_eq!(replay.back_to(0), None);
+    }
+
+    #[test]
+    fn loop_report() {
+        let processor = Processor::from_file("./src/day08/input_test.txt");
+
+        assert_eq!(processor.find_loop(), Some(LoopReport {
+            cycle: vec![1, 2, 6, 7, 3, 4],
+            lead_in: 1,
+            accumulator_delta: 5,
+        }));
+
+        let (fixed, _) = processor.change_to_terminate();
+        assert_eq!(fixed.find_loop(), None);
+
+        let processor = Processor::from_source("acc -2000000000\nloop: acc +2000000000\nacc +2000000000\njmp loop").unwrap();
+        assert_eq!(processor.find_loop().map(|r| r.accumulator_delta), Some(4_000_000_000));
+    }
+
+    #[test]
+    fn part_1_loop() {
+        let report = Processor::from_file("./src/day08/input.txt").find_loop().unwrap();
+        println!("Loop of {} instructions after {} steps: {:?}", report.cycle.len(), report.lead_in, report.cycle);
+    }
+}
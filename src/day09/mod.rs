@@ -1,4 +1,8 @@
-use crate::day01::find_pair;
+mod preamble;
+mod ranges;
+mod window;
+
+use crate::day09::ranges::RangeSearch;
 
 struct Validator {
     preamble: usize
@@ -10,38 +14,17 @@ impl Validator {
     }
 
     fn check(&self, numbers: &[i64]) -> Option<i64> {
-        let n = numbers.len();
-        for i in self.preamble..n {
-            let number = numbers[i];
-            match find_pair(number,
-                            numbers[i - self.preamble],
-                            &numbers[i - self.preamble + 1..i]) {
-                Some(_) => {
-                    //println!("{} is made up of {} and {}", number, a, b);
-                }
-                None => {
-                    //println!("No pair found for {}", number);
-                    return Some(number);
-                }
-            }
-        }
-        return None;
+        self.first_invalid(numbers).map(|(_, number)| number)
+    }
+
+    /// The index and value of the first invalid number.
+    fn first_invalid(&self, numbers: &[i64]) -> Option<(usize, i64)> {
+        self.invalid_numbers(numbers.iter().copied()).next()
     }
 }
 
 pub fn find_contiguous_set_in_list(list: &[i64], target: i64) -> Option<&[i64]> {
-    let n = list.len();
-    for i in 0..n {
-        for j in i + 2..n {
-            let sum: i64 = list[i..j].iter().sum();
-            if sum == target {
-                return Some(&list[i..j]);
-            } else if sum > target {
-                break;
-            }
-        }
-    }
-    return None;
+    RangeSearch::new().first(list, target).map(|range| &list[range])
 }
 
 #[cfg(test)]
@@ -73,7 +56,7 @@ mod tests {
         let numbers = vec![35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102,
                            117, 150, 182, 127, 219, 299, 277, 309, 576];
         let r = find_contiguous_set_in_list(&numbers, 127).unwrap();
-        println!("{:?}", &r);
+        assert_eq!(r, &[15, 25, 47, 40]);
     }
 
     #[test]
@@ -0,0 +1,80 @@
+use std::cmp::Reverse;
+use std::ops::RangeInclusive;
+use super::Validator;
+
+/// How far a stream validates with a given preamble length.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Candidate {
+    pub preamble: usize,
+    /// Index of the first invalid number, or the length of the stream if there is none.
+    pub reached: usize,
+    pub first_invalid: Option<i64>,
+}
+
+impl Candidate {
+    /// Numbers that were checked and found valid.
+    pub fn validated(&self) -> usize {
+        self.reached - self.preamble
+    }
+}
+
+/// The order `detect_preamble` returns candidates in, ties go to the shorter preamble.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Ranking {
+    /// Furthest into the stream first.
+    Reached,
+    /// Most numbers found valid first.
+    Validated,
+}
+
+/// Tries each preamble length shorter than the stream, best first.
+pub fn detect_preamble(numbers: &[i64], lengths: RangeInclusive<usize>, ranking: Ranking) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = lengths
+        .filter(|preamble| *preamble > 0 && *preamble < numbers.len())
+        .map(|preamble| match Validator::new(preamble).first_invalid(numbers) {
+            Some((reached, number)) => Candidate { preamble, reached, first_invalid: Some(number) },
+            None => Candidate { preamble, reached: numbers.len(), first_invalid: None },
+        })
+        .collect();
+
+    match ranking {
+        Ranking::Reached => candidates.sort_by_key(|c| (Reverse(c.reached), c.preamble)),
+        Ranking::Validated => candidates.sort_by_key(|c| (Reverse(c.validated()), c.preamble)),
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::read_file;
+
+    #[test]
+    fn detect_example() {
+        let numbers = vec![35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102,
+                           117, 150, 182, 127, 219, 299, 277, 309, 576];
+        let preambles = |candidates: &[Candidate]| candidates.iter().map(|c| c.preamble).collect::<Vec<_>>();
+
+        let candidates = detect_preamble(&numbers, 1..=30, Ranking::Reached);
+        assert_eq!(candidates.len(), 19);
+        assert_eq!(candidates[0], Candidate { preamble: 8, reached: 20, first_invalid: None });
+        assert_eq!(candidates[0].validated(), 12);
+        assert_eq!(preambles(&candidates[11..15]), vec![19, 5, 6, 7]);
+        assert_eq!(candidates[12], Candidate { preamble: 5, reached: 14, first_invalid: Some(127) });
+
+        let candidates = detect_preamble(&numbers, 1..=30, Ranking::Validated);
+        assert_eq!(preambles(&candidates[..6]), vec![8, 9, 10, 5, 11, 6]);
+        assert_eq!(preambles(&candidates[15..]), vec![1, 2, 3, 4]);
+
+        assert!(detect_preamble(&numbers[..3], 0..=5, Ranking::Reached).iter().all(|c| c.preamble < 3 && c.preamble > 0));
+    }
+
+    #[test]
+    fn detect_input() {
+        let numbers: Vec<i64> = read_file("./src/day09/input.txt").unwrap();
+        let expected = Candidate { preamble: 25, reached: 616, first_invalid: Some(542529149) };
+
+        assert_eq!(detect_preamble(&numbers, 2..=50, Ranking::Reached)[0], expected);
+        assert_eq!(detect_preamble(&numbers, 2..=50, Ranking::Validated)[0], expected);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Finds contiguous ranges of a list that add up to a target. Ranges are reported by
+/// start and then end, and empty ranges are never reported.
+pub struct RangeSearch {
+    min_len: usize,
+}
+
+impl RangeSearch {
+    /// Ranges of at least two numbers, as the puzzle asks for.
+    pub fn new() -> Self {
+        Self { min_len: 2 }
+    }
+
+    pub fn with_min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len.max(1);
+        self
+    }
+
+    pub fn first(&self, list: &[i64], target: i64) -> Option<Range<usize>> {
+        if list.iter().all(|n| *n >= 0) {
+            self.two_pointer(list, target, true).into_iter().next()
+        } else {
+            self.first_with_prefix_map(list, target)
+        }
+    }
+
+    pub fn all(&self, list: &[i64], target: i64) -> Vec<Range<usize>> {
+        if list.iter().all(|n| *n >= 0) {
+            self.two_pointer(list, target, false)
+        } else {
+            self.all_with_prefix_map(list, target)
+        }
+    }
+
+    /// With no negative numbers the prefix sums never decrease, so the first end where
+    /// the sum reaches the target only moves forward as the start does. Only zeros can
+    /// make more than one end match.
+    fn two_pointer(&self, list: &[i64], target: i64, first_only: bool) -> Vec<Range<usize>> {
+        let prefix = prefix_sums(list);
+        let target = target as i128;
+        let mut ranges = Vec::new();
+        let mut reach = 0;
+
+        for start in 0..list.len() {
+            reach = reach.max(start);
+            while reach < list.len() && prefix[reach] - prefix[start] < target {
+                reach += 1;
+            }
+            let mut end = reach.max(start + self.min_len);
+            while end <= list.len() && prefix[end] - prefix[start] == target {
+                ranges.push(start..end);
+                if first_only {
+                    return ranges;
+                }
+                end += 1;
+            }
+        }
+        ranges
+    }
+
+    /// Only the first position of each prefix sum can start the earliest range ending
+    /// at a given position, later ones start after it.
+    fn first_with_prefix_map(&self, list: &[i64], target: i64) -> Option<Range<usize>> {
+        let prefix = prefix_sums(list);
+        let mut first_seen: HashMap<i128, usize> = HashMap::new();
+        let mut best: Option<Range<usize>> = None;
+
+        for end in self.min_len..=list.len() {
+            first_seen.entry(prefix[end - self.min_len]).or_insert(end - self.min_len);
+            if let Some(start) = first_seen.get(&(prefix[end] - target as i128)) {
+                if best.as_ref().is_none_or(|b| *start < b.start) {
+                    best = Some(*start..end);
+                }
+            }
+        }
+        best
+    }
+
+    fn all_with_prefix_map(&self, list: &[i64], target: i64) -> Vec<Range<usize>> {
+        let prefix = prefix_sums(list);
+        let mut starts: HashMap<i128, Vec<usize>> = HashMap::new();
+        let mut ranges = Vec::new();
+
+        for end in self.min_len..=list.len() {
+            starts.entry(prefix[end - self.min_len]).or_default().push(end - self.min_len);
+            if let Some(found) = starts.get(&(prefix[end] - target as i128)) {
+                ranges.extend(found.iter().map(|start| *start..end));
+            }
+        }
+        ranges.sort_by_key(|r| (r.start, r.end));
+        ranges
+    }
+}
+
+/// `prefix[i]` is the sum of the first `i` numbers, wide enough not to overflow.
+fn prefix_sums(list: &[i64]) -> Vec<i128> {
+    let mut prefix = Vec::with_capacity(list.len() + 1);
+    prefix.push(0);
+    for n in list {
+        prefix.push(prefix.last().unwrap() + *n as i128);
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn brute_force(list: &[i64], target: i64, min_len: usize) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        for start in 0..list.len() {
+            for end in start + min_len.max(1)..=list.len() {
+                if list[start..end].iter().sum::<i64>() == target {
+                    ranges.push(start..end);
+                }
+            }
+        }
+        ranges
+    }
+
+    #[test]
+    fn example() {
+        let numbers = vec![35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102,
+                           117, 150, 182, 127, 219, 299, 277, 309, 576];
+
+        assert_eq!(RangeSearch::new().first(&numbers, 127), Some(2..6));
+        assert_eq!(RangeSearch::new().all(&numbers, 127), vec![2..6]);
+        assert_eq!(RangeSearch::new().with_min_len(1).all(&numbers, 127), vec![2..6, 14..15]);
+        assert_eq!(RangeSearch::new().with_min_len(5).first(&numbers, 127), None);
+        assert_eq!(RangeSearch::new().all(&numbers, 309 + 576), vec![16..19, 18..20]);
+    }
+
+    #[test]
+    fn zeros_and_negatives() {
+        assert_eq!(RangeSearch::new().all(&[0, 3, 0, 0], 3), vec![0..2, 0..3, 0..4, 1..3, 1..4]);
+        assert_eq!(RangeSearch::new().all(&[0, 0], 0), vec![0..2]);
+        assert_eq!(RangeSearch::new().all(&[5, -2, 2, 4, -2], 4), vec![1..4, 2..5]);
+        assert_eq!(RangeSearch::new().first(&[5, -2, 2, 4], 5), Some(0..3));
+        assert_eq!(RangeSearch::new().first(&[1, -1, 7], 7), Some(0..3));
+        assert_eq!(RangeSearch::new().first(&[], 0), None);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        fn property(list: Vec<i8>, target: i8, min_len: u8) -> bool {
+            let min_len = min_len as usize % 4;
+            let search = RangeSearch::new().with_min_len(min_len);
+            let signed: Vec<i64> = list.iter().map(|n| *n as i64 % 8).collect();
+            let unsigned: Vec<i64> = signed.iter().map(|n| n.abs()).collect();
+            let target = target as i64 % 16;
+
+            [signed, unsigned].iter().all(|list| {
+                let expected = brute_force(list, target, min_len);
+                search.all(list, target) == expected && search.first(list, target) == expected.first().cloned()
+            })
+        }
+        quickcheck(property as fn(Vec<i8>, i8, u8) -> bool);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::{HashMap, VecDeque};
+use super::Validator;
+
+/// The last `size` numbers, with a count of each value for constant-time lookups.
+pub struct Window {
+    size: usize,
+    numbers: VecDeque<i64>,
+    counts: HashMap<i64, usize>,
+}
+
+impl Window {
+    pub fn new(size: usize) -> Self {
+        Self { size, numbers: VecDeque::with_capacity(size + 1), counts: HashMap::new() }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.numbers.len() == self.size
+    }
+
+    /// Adds a number, dropping the oldest one once the window is full.
+    pub fn push(&mut self, number: i64) {
+        self.numbers.push_back(number);
+        *self.counts.entry(number).or_insert(0) += 1;
+
+        if self.numbers.len() > self.size {
+            let oldest = self.numbers.pop_front().unwrap();
+            let count = self.counts.get_mut(&oldest).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&oldest);
+            }
+        }
+    }
+
+    /// Two numbers at different positions in the window that add up to `target`.
+    pub fn find_pair(&self, target: i64) -> Option<(i64, i64)> {
+        self.numbers.iter()
+            .filter_map(|a| target.checked_sub(*a).map(|b| (*a, b)))
+            .find(|(a, b)| {
+                let needed = if a == b { 2 } else { 1 };
+                self.counts.get(b).is_some_and(|count| *count >= needed)
+            })
+    }
+}
+
+/// Every number that isn't the sum of two of the numbers before it, with its index.
+pub struct InvalidNumbers<I> {
+    numbers: I,
+    window: Window,
+    index: usize,
+}
+
+impl<I: Iterator<Item=i64>> Iterator for InvalidNumbers<I> {
+    type Item = (usize, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for number in &mut self.numbers {
+            let index = self.index;
+            self.index += 1;
+            let invalid = self.window.is_full() && self.window.find_pair(number).is_none();
+            self.window.push(number);
+            if invalid {
+                return Some((index, number));
+            }
+        }
+        None
+    }
+}
+
+impl Validator {
+    pub fn invalid_numbers<I: IntoIterator<Item=i64>>(&self, numbers: I) -> InvalidNumbers<I::IntoIter> {
+        InvalidNumbers { numbers: numbers.into_iter(), window: Window::new(self.preamble), index: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day01::find_pair;
+    use crate::util::read_file;
+
+    #[test]
+    fn window_pairs() {
+        let mut window = Window::new(3);
+        for n in &[5, 1, 5] {
+            window.push(*n);
+        }
+
+        assert!(window.is_full());
+        assert_eq!(window.find_pair(10), Some((5, 5)));
+        assert_eq!(window.find_pair(6), Some((5, 1)));
+        assert_eq!(window.find_pair(2), None);
+
+        window.push(3);
+        assert_eq!(window.find_pair(10), None);
+        assert_eq!(window.find_pair(8), Some((5, 3)));
+        assert_eq!(window.find_pair(i64::MIN), None);
+    }
+
+    #[test]
+    fn all_invalid_numbers() {
+        let numbers = vec![35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102,
+                           117, 150, 182, 127, 219, 299, 277, 309, 576];
+        let invalid: Vec<(usize, i64)> = Validator::new(5).invalid_numbers(numbers).collect();
+        assert_eq!(invalid, vec![(14, 127)]);
+
+        let invalid: Vec<(usize, i64)> = Validator::new(2).invalid_numbers(vec![1, 2, 3, 4, 7, 11, 100, 1]).collect();
+        assert_eq!(invalid, vec![(3, 4), (6, 100), (7, 1)]);
+    }
+
+    #[test]
+    fn input_matches_find_pair() {
+        let numbers: Vec<i64> = read_file("./src/day09/input.txt").unwrap();
+        let expected: Vec<(usize, i64)> = (25..numbers.len())
+            .filter(|i| find_pair(numbers[*i], numbers[i - 25], &numbers[i - 24..*i]).is_none())
+            .map(|i| (i, numbers[i]))
+            .collect();
+        let invalid: Vec<(usize, i64)> = Validator::new(25).invalid_numbers(numbers.iter().copied()).collect();
+
+        assert_eq!(invalid, expected);
+        assert_eq!(invalid[0].1, 542529149);
+    }
+}
@@ -0,0 +1,205 @@
+use num_bigint::{BigUint, RandBigInt};
+use rand::Rng;
+use super::chain::AdapterChain;
+
+/// Every arrangement of a chain, as the adapter ratings used, in lexicographic order of
+/// the ratings from the outlet to the device.
+pub struct Arrangements<'a> {
+    chain: &'a AdapterChain,
+    completions: Vec<BigUint>,
+    /// Positions from the outlet to the device of the last arrangement returned.
+    path: Vec<usize>,
+    started: bool,
+}
+
+impl<'a> Arrangements<'a> {
+    /// Follows the first successor that can still reach the device until it does.
+    fn descend(&mut self) {
+        let device = self.chain.ratings().len() - 1;
+        while let Some(&last) = self.path.last().filter(|p| **p != device) {
+            let next = self.chain.successors(last)
+                .find(|j| self.completions[*j] > BigUint::from(0u32))
+                .unwrap();
+            self.path.push(next);
+        }
+    }
+
+    fn advance(&mut self) -> bool {
+        while let Some(last) = self.path.pop() {
+            let parent = match self.path.last() {
+                Some(parent) => *parent,
+                None => return false,
+            };
+            let next = self.chain.successors(parent)
+                .find(|j| *j > last && self.completions[*j] > BigUint::from(0u32));
+            if let Some(next) = next {
+                self.path.push(next);
+                self.descend();
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = Vec<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let found = if self.started {
+            self.advance()
+        } else {
+            self.started = true;
+            if self.completions[0] > BigUint::from(0u32) {
+                self.path.push(0);
+                self.descend();
+                true
+            } else {
+                false
+            }
+        };
+
+        if found {
+            let ratings = self.chain.ratings();
+            Some(self.path[1..self.path.len() - 1].iter().map(|p| ratings[*p]).collect())
+        } else {
+            None
+        }
+    }
+}
+
+impl AdapterChain {
+    /// Lazily lists every arrangement, which is only practical for small inputs.
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        Arrangements { chain: self, completions: self.completions(), path: Vec::new(), started: false }
+    }
+
+    /// The arrangement `k` places into the order of `arrangements`, counting from 0.
+    pub fn nth_arrangement(&self, k: &BigUint) -> Option<Vec<i32>> {
+        self.nth_with(&self.completions(), k.clone())
+    }
+
+    /// Skips whole subtrees by their number of completions instead of listing them.
+    fn nth_with(&self, completions: &[BigUint], mut k: BigUint) -> Option<Vec<i32>> {
+        if k >= completions[0] {
+            return None;
+        }
+        let device = self.ratings().len() - 1;
+        let mut arrangement = Vec::new();
+        let mut position = 0;
+
+        while position != device {
+            for next in self.successors(position) {
+                if k < completions[next] {
+                    position = next;
+                    break;
+                }
+                k -= &completions[next];
+            }
+            if position != device {
+                arrangement.push(self.ratings()[position]);
+            }
+        }
+        Some(arrangement)
+    }
+
+    /// A uniformly random arrangement, or `None` if there are none. Picking a random index
+    /// weights each step by how many arrangements continue from it.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Vec<i32>> {
+        self.samples(rng, 1).pop()
+    }
+
+    pub fn samples<R: Rng + ?Sized>(&self, rng: &mut R, n: usize) -> Vec<Vec<i32>> {
+        let completions = self.completions();
+        if completions[0] == BigUint::from(0u32) {
+            return Vec::new();
+        }
+        (0..n)
+            .map(|_| {
+                let k = rng.gen_biguint_below(&completions[0]);
+                self.nth_with(&completions, k).unwrap()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::util::read_file;
+
+    fn small_example() -> AdapterChain {
+        AdapterChain::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4]).unwrap()
+    }
+
+    fn is_valid(chain: &AdapterChain, arrangement: &[i32]) -> bool {
+        let ratings = chain.ratings();
+        let mut full = vec![0];
+        full.extend_from_slice(arrangement);
+        full.push(*ratings.last().unwrap());
+        full.windows(2).all(|w| (1..=3).contains(&(w[1] - w[0])))
+            && arrangement.iter().all(|r| ratings.contains(r))
+    }
+
+    #[test]
+    fn iterate_small_example() {
+        let chain = small_example();
+        let all: Vec<Vec<i32>> = chain.arrangements().collect();
+
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+        assert_eq!(all[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+        assert!(all.iter().all(|a| is_valid(&chain, a)));
+
+        for (k, arrangement) in all.iter().enumerate() {
+            assert_eq!(chain.nth_arrangement(&BigUint::from(k)).as_ref(), Some(arrangement));
+        }
+        assert_eq!(chain.nth_arrangement(&BigUint::from(8u32)), None);
+    }
+
+    #[test]
+    fn iterate_matches_count() {
+        let chain = AdapterChain::new(&[28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8, 17, 7, 9, 4, 2, 34, 10, 3]).unwrap();
+        assert_eq!(BigUint::from(chain.arrangements().count()), chain.count());
+
+        let chain = AdapterChain::new(&[1, 3, 4]).unwrap();
+        assert_eq!(chain.arrangements().collect::<Vec<_>>(), vec![vec![1, 3, 4], vec![1, 4], vec![3, 4]]);
+
+        let chain = AdapterChain::new(&[1, 3]).unwrap().with_gaps(&[1]).unwrap();
+        assert_eq!(chain.arrangements().next(), None);
+        assert_eq!(chain.sample(&mut StdRng::seed_from_u64(1)), None);
+    }
+
+    #[test]
+    fn uniform_samples() {
+        let chain = small_example();
+        let mut rng = StdRng::seed_from_u64(2020);
+        let mut seen: HashMap<Vec<i32>, usize> = HashMap::new();
+        for arrangement in chain.samples(&mut rng, 8000) {
+            *seen.entry(arrangement).or_insert(0) += 1;
+        }
+
+        assert_eq!(seen.len(), 8);
+        assert!(seen.values().all(|n| (800..1200).contains(n)), "{:?}", seen);
+    }
+
+    #[test]
+    fn sample_input() {
+        let adapters: Vec<i32> = read_file("./src/day10/input.txt").unwrap();
+        let chain = AdapterChain::new(&adapters).unwrap();
+        let mut rng = StdRng::seed_from_u64(10);
+
+        assert!(chain.samples(&mut rng, 100).iter().all(|a| is_valid(&chain, a)));
+        let last = chain.nth_arrangement(&(chain.count() - 1u32)).unwrap();
+        assert!(is_valid(&chain, &last));
+        assert_eq!(chain.nth_arrangement(&BigUint::from(0u32)), Some({
+            let mut sorted = adapters.clone();
+            sorted.sort_unstable();
+            sorted
+        }));
+    }
+}
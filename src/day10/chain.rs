@@ -0,0 +1,145 @@
+use std::collections::BTreeSet;
+use num_bigint::BigUint;
+use super::report::ChainError;
+
+/// The outlet, the adapters in order and the device, which is rated the largest allowed gap
+/// above the highest adapter. An arrangement is any subset of the adapters where every step from the outlet
+/// to the device is one of the allowed gaps.
+pub struct AdapterChain {
+    ratings: Vec<i32>,
+    gaps: BTreeSet<i32>,
+}
+
+impl AdapterChain {
+    /// Fails with the highest adapter if the device's rating wouldn't fit in an `i32`.
+    pub fn new(adapters: &[i32]) -> Result<Self, ChainError> {
+        let mut ratings = Vec::with_capacity(adapters.len() + 2);
+        ratings.push(0);
+        ratings.extend_from_slice(adapters);
+        ratings[1..].sort_unstable();
+        let mut chain = Self { ratings, gaps: (1..=3).collect() };
+        chain.push_device()?;
+        Ok(chain)
+    }
+
+    /// Moves the device to the largest of the new gaps above the highest adapter.
+    pub fn with_gaps(mut self, gaps: &[i32]) -> Result<Self, ChainError> {
+        self.gaps = gaps.iter().copied().collect();
+        self.ratings.pop();
+        self.push_device()?;
+        Ok(self)
+    }
+
+    fn largest_gap(&self) -> i32 {
+        self.gaps.iter().next_back().copied().unwrap_or(0)
+    }
+
+    fn push_device(&mut self) -> Result<(), ChainError> {
+        let highest = *self.ratings.last().unwrap();
+        let device = highest.checked_add(self.largest_gap()).ok_or(ChainError::InvalidRating(highest))?;
+        self.ratings.push(device);
+        Ok(())
+    }
+
+    /// Outlet first, device last.
+    pub fn ratings(&self) -> &[i32] {
+        &self.ratings
+    }
+
+    /// Positions that can follow position `i`, in order.
+    pub fn successors(&self, i: usize) -> impl Iterator<Item=usize> + '_ {
+        let from = self.ratings[i];
+        let largest = self.largest_gap();
+        (i + 1..self.ratings.len())
+            .take_while(move |j| self.ratings[*j] - from <= largest)
+            .filter(move |j| self.gaps.contains(&(self.ratings[*j] - from)))
+    }
+
+    /// For each position, the number of ways to continue from there to the device.
+    pub fn completions(&self) -> Vec<BigUint> {
+        let n = self.ratings.len();
+        let mut ways = vec![BigUint::from(0u32); n];
+        ways[n - 1] = BigUint::from(1u32);
+        for i in (0..n - 1).rev() {
+            ways[i] = self.successors(i).map(|j| &ways[j]).sum();
+        }
+        ways
+    }
+
+    pub fn count(&self) -> BigUint {
+        self.completions().swap_remove(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day10::{combinations, count_consecutive_ones, differences};
+    use crate::util::read_file;
+
+    fn small_example() -> Vec<i32> {
+        vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4]
+    }
+
+    fn larger_example() -> Vec<i32> {
+        vec![28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8, 17, 7, 9, 4, 2, 34, 10, 3]
+    }
+
+    fn by_runs_of_ones(adapters: &[i32]) -> BigUint {
+        let chain = AdapterChain::new(adapters).unwrap();
+        count_consecutive_ones(&differences(&chain.ratings()[1..]))
+            .iter()
+            .map(|n| BigUint::from(combinations(*n)))
+            .product()
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(AdapterChain::new(&small_example()).unwrap().count(), BigUint::from(8u32));
+        assert_eq!(AdapterChain::new(&larger_example()).unwrap().count(), BigUint::from(19208u32));
+        assert_eq!(by_runs_of_ones(&small_example()), BigUint::from(8u32));
+        assert_eq!(by_runs_of_ones(&larger_example()), BigUint::from(19208u32));
+    }
+
+    #[test]
+    fn gaps_of_two() {
+        let chain = AdapterChain::new(&[1, 3, 4]).unwrap();
+        assert_eq!(chain.ratings(), &[0, 1, 3, 4, 7]);
+        assert_eq!(chain.count(), BigUint::from(3u32));
+        assert_eq!(by_runs_of_ones(&[1, 3, 4]), BigUint::from(1u32));
+    }
+
+    #[test]
+    fn other_gaps() {
+        assert_eq!(AdapterChain::new(&[1, 2, 3]).unwrap().with_gaps(&[1]).unwrap().count(), BigUint::from(1u32));
+        assert_eq!(AdapterChain::new(&[1, 3]).unwrap().with_gaps(&[1]).unwrap().count(), BigUint::from(0u32));
+        assert_eq!(AdapterChain::new(&[2, 4]).unwrap().with_gaps(&[2]).unwrap().ratings(), &[0, 2, 4, 6]);
+        assert_eq!(AdapterChain::new(&[3, 6]).unwrap().with_gaps(&[3]).unwrap().count(), BigUint::from(1u32));
+        assert_eq!(AdapterChain::new(&[1, 2, 3, 4]).unwrap().with_gaps(&[1, 2, 3, 4]).unwrap().count(), BigUint::from(8u32));
+        assert_eq!(AdapterChain::new(&[1, 2, 3, 4]).unwrap().with_gaps(&[1, 3]).unwrap().count(), BigUint::from(3u32));
+        assert_eq!(AdapterChain::new(&[1, 2, 3, 4]).unwrap().with_gaps(&[]).unwrap().count(), BigUint::from(0u32));
+    }
+
+    #[test]
+    fn device_overflow() {
+        assert_eq!(AdapterChain::new(&[1, i32::MAX - 3]).unwrap().ratings().last(), Some(&i32::MAX));
+        assert_eq!(AdapterChain::new(&[1, i32::MAX - 2]).err(), Some(ChainError::InvalidRating(i32::MAX - 2)));
+        assert_eq!(AdapterChain::new(&[i32::MAX - 3]).unwrap().with_gaps(&[1, 2]).unwrap().ratings().last(), Some(&(i32::MAX - 1)));
+        assert_eq!(AdapterChain::new(&[i32::MAX - 3]).unwrap().with_gaps(&[1, 4]).err(), Some(ChainError::InvalidRating(i32::MAX - 3)));
+    }
+
+    #[test]
+    fn part_2() {
+        let adapters: Vec<i32> = read_file("./src/day10/input.txt").unwrap();
+        let count = AdapterChain::new(&adapters).unwrap().count();
+        assert_eq!(count, by_runs_of_ones(&adapters));
+        println!("Total combinations: {}", count);
+    }
+
+    #[test]
+    fn larger_than_u64() {
+        let adapters: Vec<i32> = (1..=200).collect();
+        let count = AdapterChain::new(&adapters).unwrap().count();
+        assert!(count > BigUint::from(u64::MAX));
+    }
+}
@@ -1,5 +1,10 @@
+mod arrangements;
+mod chain;
+mod report;
+
 use std::collections::HashMap;
 use std::hash::Hash;
+use crate::day10::report::{ChainError, Report};
 
 pub fn differences(input: &[i32]) -> Vec<i32> {
     let mut diffs = Vec::with_capacity(input.len());
@@ -21,12 +26,13 @@ pub fn count_entries<T>(input: &[T]) -> HashMap<T, usize>
     counts
 }
 
-fn jolts(mut input: Vec<i32>) -> (usize, usize) {
-    input.sort();
-    input.push(input.last().unwrap() + 3);
-    let diffs = differences(&input);
-    let counts = count_entries(&diffs);
-    (counts[&1], counts[&3])
+/// The number of 1 and 3 jolt differences when every adapter is used.
+fn jolts(input: Vec<i32>) -> Result<(usize, usize), ChainError> {
+    let report = Report::new(&input)?;
+    if !report.is_valid() {
+        return Err(ChainError::InvalidGaps(report.invalid));
+    }
+    Ok((report.count(1), report.count(3)))
 }
 
 fn count_consecutive_ones(diffs: &[i32]) -> Vec<usize> {
@@ -66,21 +72,24 @@ mod tests {
     #[test]
     fn jolting_difference() {
         let adapters = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
-        let (ones, threes) = jolts(adapters);
+        let (ones, threes) = jolts(adapters).unwrap();
         println!("Small example: Ones: {}, Threes: {}", ones, threes);
+        assert_eq!((ones, threes), (7, 5));
+        assert_eq!(jolts(vec![1, 2, 3]), Ok((3, 1)));
+        assert_eq!(jolts(vec![1, 8]), Err(ChainError::InvalidGaps(vec![report::Gap { from: 1, to: 8 }])));
     }
 
     #[test]
     fn larger_example() {
         let adapters = vec![28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8, 17, 7, 9, 4, 2, 34, 10, 3];
-        let (ones, threes) = jolts(adapters);
+        let (ones, threes) = jolts(adapters).unwrap();
         println!("Larger example: Ones: {}, Threes: {}", ones, threes);
     }
 
     #[test]
     fn part_1() {
         let adapters = read_file("./src/day10/input.txt").unwrap();
-        let (ones, threes) = jolts(adapters);
+        let (ones, threes) = jolts(adapters).unwrap();
         println!("Part 1: Ones: {}, Threes: {}", ones, threes);
     }
 
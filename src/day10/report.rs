@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter;
+use super::chain::AdapterChain;
+use super::{count_entries, differences};
+
+/// Two neighbouring ratings in the chain that are too far apart, or equal.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Gap {
+    pub from: i32,
+    pub to: i32,
+}
+
+impl Gap {
+    pub fn size(&self) -> i32 {
+        self.to - self.from
+    }
+
+    /// The fewest adapter ratings to add so that every step is at most 3. Equal ratings
+    /// can't be bridged, one of them has to go.
+    pub fn bridge(&self) -> Vec<i32> {
+        iter::successors(self.from.checked_add(3), |rating| rating.checked_add(3))
+            .take_while(|rating| *rating < self.to)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ChainError {
+    /// Adapters are rated above the outlet's 0 jolts, and low enough that the device's
+    /// rating 3 above them fits in an `i32`.
+    InvalidRating(i32),
+    InvalidGaps(Vec<Gap>),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::InvalidRating(rating) => write!(f, "Invalid adapter rating {}", rating),
+            ChainError::InvalidGaps(gaps) => write!(f, "{} gaps are outside 1 to 3 jolts", gaps.len()),
+        }
+    }
+}
+
+/// The differences when every adapter is used, from the outlet to the device.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Report {
+    pub histogram: BTreeMap<i32, usize>,
+    pub invalid: Vec<Gap>,
+}
+
+impl Report {
+    pub fn new(adapters: &[i32]) -> Result<Self, ChainError> {
+        if let Some(rating) = adapters.iter().find(|r| **r < 1 || **r > i32::MAX - 3) {
+            return Err(ChainError::InvalidRating(*rating));
+        }
+
+        let chain = AdapterChain::new(adapters)?;
+        let ratings = chain.ratings();
+        let histogram = count_entries(&differences(&ratings[1..])).into_iter().collect();
+        let invalid = ratings.windows(2)
+            .map(|w| Gap { from: w[0], to: w[1] })
+            .filter(|gap| !(1..=3).contains(&gap.size()))
+            .collect();
+
+        Ok(Self { histogram, invalid })
+    }
+
+    pub fn count(&self, difference: i32) -> usize {
+        self.histogram.get(&difference).copied().unwrap_or(0)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.invalid.is_empty()
+    }
+
+    /// Every rating to add to bridge the invalid gaps.
+    pub fn suggestions(&self) -> Vec<i32> {
+        self.invalid.iter().flat_map(|gap| gap.bridge()).collect()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "differences:")?;
+        for (difference, count) in &self.histogram {
+            writeln!(f, "  {}: {}", difference, count)?;
+        }
+        if self.is_valid() {
+            return writeln!(f, "invalid gaps: none");
+        }
+        writeln!(f, "invalid gaps:")?;
+        for gap in &self.invalid {
+            let bridge = gap.bridge();
+            if bridge.is_empty() {
+                writeln!(f, "  {} -> {} ({}), remove a duplicate", gap.from, gap.to, gap.size())?;
+            } else {
+                let ratings: Vec<String> = bridge.iter().map(|r| r.to_string()).collect();
+                writeln!(f, "  {} -> {} ({}), add {}", gap.from, gap.to, gap.size(), ratings.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_chain() {
+        let report = Report::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4]).unwrap();
+
+        assert_eq!(report.histogram, vec![(1, 7), (3, 5)].into_iter().collect());
+        assert!(report.is_valid());
+        assert_eq!(report.to_string(), "differences:\n  1: 7\n  3: 5\ninvalid gaps: none\n");
+    }
+
+    #[test]
+    fn invalid_gaps() {
+        let report = Report::new(&[2, 4, 4, 11, 12]).unwrap();
+
+        assert_eq!(report.histogram, vec![(0, 1), (1, 1), (2, 2), (3, 1), (7, 1)].into_iter().collect());
+        assert_eq!(report.invalid, vec![Gap { from: 4, to: 4 }, Gap { from: 4, to: 11 }]);
+        assert_eq!(report.suggestions(), vec![7, 10]);
+        assert_eq!(report.count(3), 1);
+        assert_eq!(report.count(5), 0);
+        assert!(report.to_string().ends_with("\
+invalid gaps:
+  4 -> 4 (0), remove a duplicate
+  4 -> 11 (7), add 7, 10
+"));
+    }
+
+    #[test]
+    fn bridges() {
+        assert_eq!(Gap { from: 0, to: 4 }.bridge(), vec![3]);
+        assert_eq!(Gap { from: 1, to: 7 }.bridge(), vec![4]);
+        assert_eq!(Gap { from: 1, to: 8 }.bridge(), vec![4, 7]);
+        assert!(Gap { from: 5, to: 5 }.bridge().is_empty());
+        assert_eq!(Gap { from: i32::MAX - 4, to: i32::MAX }.bridge(), vec![i32::MAX - 1]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Report::new(&[3, 0]), Err(ChainError::InvalidRating(0)));
+        assert_eq!(Report::new(&[i32::MAX - 1]), Err(ChainError::InvalidRating(i32::MAX - 1)));
+        assert!(Report::new(&[i32::MAX - 3]).is_ok());
+        assert_eq!(ChainError::InvalidGaps(vec![Gap { from: 1, to: 8 }]).to_string(), "1 gaps are outside 1 to 3 jolts");
+        assert_eq!(Report::new(&[]).map(|r| r.histogram), Ok(vec![(3, 1)].into_iter().collect()));
+    }
+}
@@ -0,0 +1,286 @@
+use std::fmt;
+use std::fmt::Write;
+
+/// A state a cell can be in, written as a single character.
+pub trait CellState: Copy + Eq {
+    fn from_char(c: char) -> Option<Self>;
+    fn to_char(self) -> char;
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum GridError {
+    Empty,
+    Ragged { row: usize, expected: usize, actual: usize },
+    InvalidCell { row: usize, column: usize, character: char },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "Empty grid"),
+            GridError::Ragged { row, expected, actual } =>
+                write!(f, "Row {} has {} cells, expected {}", row, actual, expected),
+            GridError::InvalidCell { row, column, character } =>
+                write!(f, "Invalid cell {:?} at row {}, column {}", character, row, column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Grid<S> {
+    rows: i32,
+    columns: i32,
+    cells: Vec<S>,
+}
+
+impl<S: CellState> Grid<S> {
+    pub fn parse<T: AsRef<str>>(lines: &[T]) -> Result<Self, GridError> {
+        let columns = lines.first().ok_or(GridError::Empty)?.as_ref().chars().count();
+        if columns == 0 {
+            return Err(GridError::Empty);
+        }
+        let mut cells = Vec::with_capacity(lines.len() * columns);
+
+        for (row, line) in lines.iter().enumerate() {
+            let before = cells.len();
+            for (column, character) in line.as_ref().chars().enumerate() {
+                let cell = S::from_char(character).ok_or(GridError::InvalidCell { row, column, character })?;
+                cells.push(cell);
+            }
+            if cells.len() - before != columns {
+                return Err(GridError::Ragged { row, expected: columns, actual: cells.len() - before });
+            }
+        }
+
+        Ok(Self { rows: lines.len() as i32, columns: columns as i32, cells })
+    }
+
+    pub fn rows(&self) -> i32 {
+        self.rows
+    }
+
+    pub fn columns(&self) -> i32 {
+        self.columns
+    }
+
+    /// The cell at a position, `None` outside the grid.
+    pub fn get(&self, row: i32, column: i32) -> Option<S> {
+        if row < 0 || row >= self.rows || column < 0 || column >= self.columns {
+            return None;
+        }
+        Some(self.cells[(row * self.columns + column) as usize])
+    }
+
+    pub fn count(&self, state: S) -> usize {
+        self.cells.iter().filter(|c| **c == state).count()
+    }
+}
+
+impl<S: CellState> fmt::Display for Grid<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.cells.chunks(self.columns as usize) {
+            for cell in row {
+                f.write_char(cell.to_char())?;
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+pub const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, -1), (-1, 0), (-1, 1),
+    (0, -1), (0, 1),
+    (1, -1), (1, 0), (1, 1)
+];
+
+/// Which cells a cell looks at when deciding its next state.
+pub trait Neighbourhood<S> {
+    /// Pushes the states of the neighbours of a cell onto `out`.
+    fn neighbours(&self, grid: &Grid<S>, row: i32, column: i32, out: &mut Vec<S>);
+}
+
+/// The eight adjacent cells.
+pub struct Moore;
+
+impl<S: CellState> Neighbourhood<S> for Moore {
+    fn neighbours(&self, grid: &Grid<S>, row: i32, column: i32, out: &mut Vec<S>) {
+        out.extend(DIRECTIONS.iter().filter_map(|(dy, dx)| grid.get(row + dy, column + dx)));
+    }
+}
+
+/// The first cell in each of the eight directions that isn't `transparent`.
+pub struct LineOfSight<S> {
+    pub transparent: S,
+}
+
+impl<S: CellState> Neighbourhood<S> for LineOfSight<S> {
+    fn neighbours(&self, grid: &Grid<S>, row: i32, column: i32, out: &mut Vec<S>) {
+        for (dy, dx) in DIRECTIONS.iter() {
+            let (mut r, mut c) = (row + dy, column + dx);
+            while let Some(cell) = grid.get(r, c) {
+                if cell != self.transparent {
+                    out.push(cell);
+                    break;
+                }
+                r += dy;
+                c += dx;
+            }
+        }
+    }
+}
+
+/// The next state of a cell from its current state and its neighbours' states.
+pub trait Rule<S> {
+    fn next(&self, cell: S, neighbours: &[S]) -> S;
+}
+
+impl<S, F: Fn(S, &[S]) -> S> Rule<S> for F {
+    fn next(&self, cell: S, neighbours: &[S]) -> S {
+        self(cell, neighbours)
+    }
+}
+
+pub struct RuleSet<S> {
+    pub neighbourhood: Box<dyn Neighbourhood<S>>,
+    pub rule: Box<dyn Rule<S>>,
+}
+
+impl<S> RuleSet<S> {
+    pub fn new<N, R>(neighbourhood: N, rule: R) -> Self
+        where N: Neighbourhood<S> + 'static, R: Rule<S> + 'static {
+        Self { neighbourhood: Box::new(neighbourhood), rule: Box::new(rule) }
+    }
+}
+
+/// Applies a rule set to every cell of a grid at once.
+pub struct Automaton<S> {
+    grid: Grid<S>,
+    rules: RuleSet<S>,
+}
+
+impl<S: CellState> Automaton<S> {
+    pub fn new(grid: Grid<S>, rules: RuleSet<S>) -> Self {
+        Self { grid, rules }
+    }
+
+    pub fn with_rules(mut self, rules: RuleSet<S>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn grid(&self) -> &Grid<S> {
+        &self.grid
+    }
+
+    /// Moves every cell to its next state, returning whether anything changed.
+    pub fn step(&mut self) -> bool {
+        let mut neighbours = Vec::with_capacity(DIRECTIONS.len());
+        let mut cells = Vec::with_capacity(self.grid.cells.len());
+
+        for row in 0..self.grid.rows {
+            for column in 0..self.grid.columns {
+                neighbours.clear();
+                self.rules.neighbourhood.neighbours(&self.grid, row, column, &mut neighbours);
+                let cell = self.grid.get(row, column).unwrap();
+                cells.push(self.rules.rule.next(cell, &neighbours));
+            }
+        }
+
+        let changed = cells != self.grid.cells;
+        self.grid.cells = cells;
+        changed
+    }
+
+    /// Steps until nothing changes, returning the number of steps that changed something,
+    /// or `None` if more than `limit` steps did.
+    pub fn run_until_stable(&mut self, limit: usize) -> Option<usize> {
+        (0..=limit).find(|_| !self.step())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    enum Cell {
+        Off,
+        On,
+    }
+
+    impl CellState for Cell {
+        fn from_char(c: char) -> Option<Self> {
+            match c {
+                '.' => Some(Cell::Off),
+                'o' => Some(Cell::On),
+                _ => None,
+            }
+        }
+
+        fn to_char(self) -> char {
+            match self {
+                Cell::Off => '.',
+                Cell::On => 'o',
+            }
+        }
+    }
+
+    /// Turns on cells next to exactly one other cell that is on.
+    fn spread(cell: Cell, neighbours: &[Cell]) -> Cell {
+        match neighbours.iter().filter(|n| **n == Cell::On).count() {
+            1 => Cell::On,
+            _ => cell,
+        }
+    }
+
+    #[test]
+    fn parse_grid() {
+        let grid: Grid<Cell> = Grid::parse(&[".o.", "..o"]).unwrap();
+
+        assert_eq!((grid.rows(), grid.columns()), (2, 3));
+        assert_eq!(grid.get(1, 2), Some(Cell::On));
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.count(Cell::On), 2);
+        assert_eq!(grid.to_string(), ".o.\n..o\n");
+
+        let empty: [&str; 0] = [];
+        assert_eq!(Grid::<Cell>::parse(&empty), Err(GridError::Empty));
+        assert_eq!(Grid::<Cell>::parse(&["", ""]), Err(GridError::Empty));
+        assert_eq!(Grid::<Cell>::parse(&["..", "."]), Err(GridError::Ragged { row: 1, expected: 2, actual: 1 }));
+        assert_eq!(Grid::<Cell>::parse(&[".x"]), Err(GridError::InvalidCell { row: 0, column: 1, character: 'x' }));
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        let grid: Grid<Cell> = Grid::parse(&["o...o", ".....", "o.o.."]).unwrap();
+        let mut out = Vec::new();
+
+        Moore.neighbours(&grid, 1, 1, &mut out);
+        assert_eq!(out.iter().filter(|c| **c == Cell::On).count(), 3);
+
+        out.clear();
+        Moore.neighbours(&grid, 0, 0, &mut out);
+        assert_eq!(out.len(), 3);
+
+        out.clear();
+        LineOfSight { transparent: Cell::Off }.neighbours(&grid, 0, 2, &mut out);
+        assert_eq!(out, vec![Cell::On, Cell::On, Cell::On, Cell::On]);
+    }
+
+    #[test]
+    fn closure_rule() {
+        let grid = Grid::parse(&["o...."]).unwrap();
+        let mut automaton = Automaton::new(grid.clone(), RuleSet::new(Moore, spread));
+
+        assert!(automaton.step());
+        assert_eq!(automaton.grid().to_string(), "oo...\n");
+        assert_eq!(automaton.run_until_stable(10), Some(3));
+        assert_eq!(automaton.grid().to_string(), "ooooo\n");
+        assert_eq!(Automaton::new(grid, RuleSet::new(Moore, spread)).run_until_stable(3), None);
+
+        let mut automaton = automaton.with_rules(RuleSet::new(Moore, |_: Cell, _: &[Cell]| Cell::Off));
+        assert_eq!(automaton.run_until_stable(0), None);
+        assert_eq!(automaton.grid().count(Cell::On), 0);
+    }
+}
@@ -0,0 +1,155 @@
+use super::automaton::{CellState, LineOfSight, Moore, Rule, RuleSet};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Seat {
+    Floor,
+    Empty,
+    Taken,
+}
+
+impl CellState for Seat {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Seat::Floor),
+            'L' => Some(Seat::Empty),
+            '#' => Some(Seat::Taken),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Seat::Floor => '.',
+            Seat::Empty => 'L',
+            Seat::Taken => '#',
+        }
+    }
+}
+
+/// Empty seats with no taken neighbours are taken, and people leave taken seats with at
+/// least `tolerance` taken neighbours.
+pub struct SeatRule {
+    pub tolerance: usize,
+}
+
+impl Rule<Seat> for SeatRule {
+    fn next(&self, seat: Seat, neighbours: &[Seat]) -> Seat {
+        let taken = neighbours.iter().filter(|s| **s == Seat::Taken).count();
+        match seat {
+            Seat::Empty if taken == 0 => Seat::Taken,
+            Seat::Taken if taken >= self.tolerance => Seat::Empty,
+            _ => seat,
+        }
+    }
+}
+
+impl RuleSet<Seat> {
+    /// Looks at the adjacent seats, people leave when four of them are taken.
+    pub fn adjacent_seats() -> Self {
+        RuleSet::new(Moore, SeatRule { tolerance: 4 })
+    }
+
+    /// Looks at the first seat in each direction, people leave when five of them are taken.
+    pub fn visible_seats() -> Self {
+        RuleSet::new(LineOfSight { transparent: Seat::Floor }, SeatRule { tolerance: 5 })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Life {
+    Dead,
+    Alive,
+}
+
+impl CellState for Life {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Life::Dead),
+            '#' => Some(Life::Alive),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Life::Dead => '.',
+            Life::Alive => '#',
+        }
+    }
+}
+
+/// Which numbers of live neighbours bring a dead cell to life or keep a live one alive.
+pub struct LifeRule {
+    pub born: Vec<usize>,
+    pub survives: Vec<usize>,
+}
+
+impl Rule<Life> for LifeRule {
+    fn next(&self, cell: Life, neighbours: &[Life]) -> Life {
+        let alive = neighbours.iter().filter(|c| **c == Life::Alive).count();
+        let counts = match cell {
+            Life::Dead => &self.born,
+            Life::Alive => &self.survives,
+        };
+        if counts.contains(&alive) {
+            Life::Alive
+        } else {
+            Life::Dead
+        }
+    }
+}
+
+impl RuleSet<Life> {
+    /// Conway's Game of Life, B3/S23, with everything outside the grid dead.
+    pub fn conway() -> Self {
+        RuleSet::new(Moore, LifeRule { born: vec![3], survives: vec![2, 3] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day11::automaton::{Automaton, Grid};
+
+    fn life(lines: &[&str]) -> Automaton<Life> {
+        Automaton::new(Grid::parse(lines).unwrap(), RuleSet::conway())
+    }
+
+    #[test]
+    fn still_life() {
+        let mut block = life(&["....", ".##.", ".##.", "...."]);
+        assert_eq!(block.run_until_stable(10), Some(0));
+        assert_eq!(block.grid().count(Life::Alive), 4);
+    }
+
+    #[test]
+    fn oscillator() {
+        let mut blinker = life(&[".....", "..#..", "..#..", "..#..", "....."]);
+        let start = blinker.grid().clone();
+
+        assert!(blinker.step());
+        assert_eq!(blinker.grid().to_string(), ".....\n.....\n.###.\n.....\n.....\n");
+        assert!(blinker.step());
+        assert_eq!(blinker.grid(), &start);
+        assert_eq!(blinker.run_until_stable(20), None);
+    }
+
+    #[test]
+    fn glider() {
+        let mut glider = life(&[".#....", "..#...", "###...", "......", "......", "......"]);
+        for _ in 0..4 {
+            glider.step();
+        }
+        assert_eq!(glider.grid().to_string(), "......\n..#...\n...#..\n.###..\n......\n......\n");
+    }
+
+    #[test]
+    fn seat_rule() {
+        let rule = SeatRule { tolerance: 4 };
+        assert_eq!(rule.next(Seat::Empty, &[Seat::Empty, Seat::Floor]), Seat::Taken);
+        assert_eq!(rule.next(Seat::Empty, &[Seat::Taken]), Seat::Empty);
+        assert_eq!(rule.next(Seat::Taken, &[Seat::Taken; 3]), Seat::Taken);
+        assert_eq!(rule.next(Seat::Taken, &[Seat::Taken; 4]), Seat::Empty);
+        assert_eq!(rule.next(Seat::Floor, &[]), Seat::Floor);
+    }
+}
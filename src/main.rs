@@ -1,3 +1,5 @@
+use std::env;
+
 mod util;
 mod day01;
 mod day02;
@@ -14,4 +16,14 @@ mod day12;
 mod day13;
 mod day14;
 
-fn main() {}
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("day07") if args.len() >= 2 => day07::run_query(&args[1], &args[2..]),
+        Some("day08") if args.len() == 2 => day08::run_debugger(&args[1]),
+        _ => {
+            eprintln!("Usage: year-2020 day07 <rules file> [query]");
+            eprintln!("       year-2020 day08 <program file>");
+        }
+    }
+}